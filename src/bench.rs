@@ -0,0 +1,302 @@
+// benchmarking the solver on the test files, which hold one position per line as "moves score",
+// with moves numbered 1-7 and the score from the view of the player to play.
+// results can be written as text (like progress.txt), json or csv, and the json or csv output of an
+// earlier run can be used as a baseline to flag regressions
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use crate::{Position, Solver};
+
+pub struct TestCase {
+    pub moves: String,
+    pub score: i8,
+}
+
+// results for one test file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub file: String,
+    pub positions: usize,
+    pub accuracy: f64, // % solved correctly
+    pub mean_time: f64, // seconds
+    pub median_time: f64,
+    pub p99_time: f64,
+    pub mean_nodes: f64,
+    pub median_nodes: u64,
+    pub p99_nodes: u64,
+}
+
+// reads a test file, returns an error for any line that is not "moves score" with a valid position
+pub fn read_test_file<P: AsRef<Path>>(file_path: P) -> io::Result<Vec<TestCase>> {
+    let contents = fs::read_to_string(&file_path)?;
+    let mut cases = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() { continue }
+        let mut parts = line.split_whitespace();
+        let moves = parts.next().unwrap_or("").to_string();
+        match parts.next().map(|s| s.parse()) {
+            Some(Ok(score)) => {
+                if let Err(e) = Position::from_moves(&moves) {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("line {}: invalid position {moves}: {e}", i + 1)))
+                }
+                cases.push(TestCase { moves, score })
+            },
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("line {}: expected \"moves score\"", i + 1))),
+        }
+    }
+    Ok(cases)
+}
+
+// solves every case with a fresh solver, like a new game would, and summarizes how it went.
+// an error if a case is not a valid position, which read_test_file already checks
+pub fn run(file: &str, cases: &[TestCase], new_solver: impl Fn() -> Solver) -> Result<Summary, String> {
+    let mut num_correct = 0;
+    let mut times = Vec::new();
+    let mut nodes = Vec::new();
+
+    for case in cases {
+        let mut pos = Position::from_moves(&case.moves).map_err(|e| format!("{file}: invalid test position {}: {e}", case.moves))?;
+        let mut solver = new_solver();
+        let start = Instant::now();
+        let (predicted_score, num_positions) = solver.score(&mut pos).unwrap();
+        times.push(start.elapsed());
+        nodes.push(num_positions);
+        if predicted_score == case.score { num_correct += 1 }
+    }
+    Ok(summarize(file, num_correct, times, nodes))
+}
+
+fn summarize(file: &str, num_correct: usize, mut times: Vec<Duration>, mut nodes: Vec<u64>) -> Summary {
+    let n = times.len();
+    times.sort();
+    nodes.sort();
+
+    // the value that the given fraction of samples are at or below
+    let rank = |fraction: f64| ((fraction * n as f64).ceil() as usize).clamp(1, n.max(1)) - 1;
+
+    Summary {
+        file: file.to_string(),
+        positions: n,
+        accuracy: if n == 0 { 0.0 } else { 100.0 * num_correct as f64 / n as f64 },
+        mean_time: if n == 0 { 0.0 } else { times.iter().sum::<Duration>().as_secs_f64() / n as f64 },
+        median_time: times.get(rank(0.5)).map_or(0.0, Duration::as_secs_f64),
+        p99_time: times.get(rank(0.99)).map_or(0.0, Duration::as_secs_f64),
+        mean_nodes: if n == 0 { 0.0 } else { nodes.iter().sum::<u64>() as f64 / n as f64 },
+        median_nodes: nodes.get(rank(0.5)).copied().unwrap_or(0),
+        p99_nodes: nodes.get(rank(0.99)).copied().unwrap_or(0),
+    }
+}
+
+// one line per file, in the format of progress.txt followed by the median and p99
+pub fn to_text(summaries: &[Summary]) -> String {
+    summaries.iter().map(|s| format!(
+        "{}: {}%, {:.6} seconds, {} positions (median {:.6} seconds, {} positions; p99 {:.6} seconds, {} positions)\n",
+        s.file, s.accuracy, s.mean_time, with_commas(s.mean_nodes.round() as u64),
+        s.median_time, with_commas(s.median_nodes), s.p99_time, with_commas(s.p99_nodes),
+    )).collect()
+}
+
+pub fn to_json(summaries: &[Summary]) -> String {
+    let objects: Vec<String> = summaries.iter().map(|s| format!(
+        "  {{\"file\": \"{}\", \"positions\": {}, \"accuracy\": {}, \"mean_time\": {}, \"median_time\": {}, \"p99_time\": {}, \"mean_nodes\": {}, \"median_nodes\": {}, \"p99_nodes\": {}}}",
+        s.file.replace('\\', "\\\\").replace('"', "\\\""), s.positions, s.accuracy, s.mean_time, s.median_time, s.p99_time, s.mean_nodes, s.median_nodes, s.p99_nodes,
+    )).collect();
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+pub fn to_csv(summaries: &[Summary]) -> String {
+    let mut csv = String::from("file,positions,accuracy,mean_time,median_time,p99_time,mean_nodes,median_nodes,p99_nodes\n");
+    for s in summaries {
+        // a file name is quoted when it would not read back as one field, doubling its quotes
+        let file = if s.file.contains([',', '"']) { format!("\"{}\"", s.file.replace('"', "\"\"")) } else { s.file.clone() };
+        csv += &format!(
+            "{},{},{},{},{},{},{},{},{}\n",
+            file, s.positions, s.accuracy, s.mean_time, s.median_time, s.p99_time, s.mean_nodes, s.median_nodes, s.p99_nodes,
+        );
+    }
+    csv
+}
+
+// reads summaries written by to_json or to_csv
+pub fn parse_baseline(contents: &str) -> Result<Vec<Summary>, String> {
+    let records: Vec<Vec<(String, String)>> = if contents.trim_start().starts_with('[') {
+        // each object sits on its own line, as written by to_json
+        contents.lines().filter(|line| line.trim_start().starts_with('{')).map(|line| {
+            let object = line.trim().trim_end_matches(',').trim_start_matches('{').trim_end_matches('}');
+            split_fields(object, false).into_iter().filter_map(|field| {
+                let (key, value) = field.split_once(':')?;
+                let value = value.trim();
+                // strings are unescaped, numbers kept as they are
+                let value = match value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+                    Some(string) => {
+                        let mut chars = string.chars();
+                        let mut unescaped = String::new();
+                        while let Some(c) = chars.next() {
+                            unescaped.push(if c == '\\' { chars.next().unwrap_or(c) } else { c });
+                        }
+                        unescaped
+                    },
+                    None => value.to_string(),
+                };
+                Some((key.trim().trim_matches('"').to_string(), value))
+            }).collect()
+        }).collect()
+    } else {
+        let mut lines = contents.lines();
+        let header: Vec<&str> = lines.next().unwrap_or("").split(',').collect();
+        lines.filter(|line| !line.trim().is_empty()).map(|line| {
+            header.iter().zip(split_fields(line, true)).map(|(k, v)| {
+                // quoted fields have their quotes taken off and doubled quotes undone
+                let v = match v.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                    Some(quoted) => quoted.replace("\"\"", "\""),
+                    None => v.to_string(),
+                };
+                (k.to_string(), v)
+            }).collect()
+        }).collect()
+    };
+
+    records.into_iter().map(|record| {
+        let field = |name: &str| record.iter().find(|(k, _)| k == name).map(|(_, v)| v.as_str()).ok_or(format!("missing {name}"));
+        let number = |name: &str| field(name)?.parse::<f64>().map_err(|_| format!("{name} is not a number"));
+        Ok(Summary {
+            file: field("file")?.to_string(),
+            positions: number("positions")? as usize,
+            accuracy: number("accuracy")?,
+            mean_time: number("mean_time")?,
+            median_time: number("median_time")?,
+            p99_time: number("p99_time")?,
+            mean_nodes: number("mean_nodes")?,
+            median_nodes: number("median_nodes")? as u64,
+            p99_nodes: number("p99_nodes")? as u64,
+        })
+    }).collect()
+}
+
+// splits a line on the commas that are not inside a quoted string, keeping the quotes. in json a
+// backslash escapes the character after it, in csv a quote inside a string is doubled, which
+// leaves the string and enters it again
+fn split_fields(line: &str, csv: bool) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quoted && !csv {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if c == ',' && !quoted {
+            fields.push(&line[start..i]);
+            start = i + 1;
+        }
+    }
+    fields.push(&line[start..]);
+    fields
+}
+
+// describes every way the current results are worse than the baseline.
+// accuracy may not drop at all, mean time and nodes may not grow by more than threshold %
+pub fn regressions(current: &[Summary], baseline: &[Summary], threshold: f64) -> Vec<String> {
+    let mut found = Vec::new();
+    for now in current {
+        let Some(before) = baseline.iter().find(|b| b.file == now.file) else { continue };
+        if now.accuracy < before.accuracy {
+            found.push(format!("{}: accuracy dropped from {}% to {}%", now.file, before.accuracy, now.accuracy));
+        }
+        let limit = 1.0 + threshold / 100.0;
+        if now.mean_nodes > before.mean_nodes * limit {
+            found.push(format!("{}: mean positions grew from {:.0} to {:.0}", now.file, before.mean_nodes, now.mean_nodes));
+        }
+        if now.mean_time > before.mean_time * limit {
+            found.push(format!("{}: mean time grew from {:.6} to {:.6} seconds", now.file, before.mean_time, now.mean_time));
+        }
+    }
+    found
+}
+
+// 1234567 -> 1,234,567
+fn with_commas(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) { out.push(',') }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> Summary {
+        let times = (1..=100).map(Duration::from_millis).collect();
+        let nodes = (1..=100).collect();
+        summarize("Example", 99, times, nodes)
+    }
+
+    #[test]
+    fn test_summarize() {
+        let s = summary();
+        assert_eq!(s.positions, 100);
+        assert_eq!(s.accuracy, 99.0);
+        assert_eq!(s.median_nodes, 50);
+        assert_eq!(s.p99_nodes, 99);
+        assert_eq!(s.mean_nodes, 50.5);
+        assert!((s.median_time - 0.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_run() {
+        let cases = read_test_file("test_files/End-Easy.txt").unwrap();
+        assert_eq!(cases.len(), 1000);
+        let s = run("End-Easy", &cases[..20], Solver::new).unwrap();
+        assert_eq!(s.positions, 20);
+        assert_eq!(s.accuracy, 100.0);
+        assert!(run("Bad", &[TestCase { moves: String::from("4444444"), score: 0 }], Solver::new).unwrap_err().contains("4444444"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_positions() {
+        let path = std::env::temp_dir().join(format!("c4bench-test-{}", std::process::id()));
+        fs::write(&path, "4453 -3\n4444444 0\n").unwrap();
+        let error = read_test_file(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 2: invalid position 4444444"));
+    }
+
+    #[test]
+    fn test_text() {
+        assert!(to_text(&[summary()]).starts_with("Example: 99%, 0.050500 seconds, 51 positions (median 0.050000 seconds, 50 positions"));
+        assert_eq!(with_commas(1265912), "1,265,912");
+        assert_eq!(with_commas(56), "56");
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let summaries = vec![
+            summary(),
+            Summary { file: String::from("Other \"quoted\" \\ name\\"), ..summary() },
+            Summary { file: String::from("a, \"b\",c"), ..summary() },
+        ];
+        assert_eq!(parse_baseline(&to_json(&summaries)).unwrap(), summaries);
+        assert_eq!(parse_baseline(&to_csv(&summaries)).unwrap(), summaries);
+        assert!(parse_baseline("file,positions\nExample,x\n").is_err());
+    }
+
+    #[test]
+    fn test_regressions() {
+        let before = summary();
+        assert!(regressions(std::slice::from_ref(&before), std::slice::from_ref(&before), 5.0).is_empty());
+
+        let worse = Summary { accuracy: 98.0, mean_nodes: 60.0, ..before.clone() };
+        assert_eq!(regressions(&[worse], std::slice::from_ref(&before), 5.0).len(), 2);
+
+        let slightly_slower = Summary { mean_time: before.mean_time * 1.04, ..before.clone() };
+        assert!(regressions(&[slightly_slower], &[before], 5.0).is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+// benchmarks the solver on test files, replacing the old test_progress_check
+//
+// usage: bench [options] [files...]
+//   files                  test file paths, or names in test_files/ like End-Easy (all of test_files/ by default)
+//   --limit <n>            only use the first n positions of each file
+//   --format <format>      text (default), json or csv
+//   --baseline <file>      json or csv output of an earlier run to compare against
+//   --threshold <percent>  how much slower or more positions counts as a regression (default 10)
+//   --ordering <ordering>  move ordering for negamax, see src/ordering.rs: center, threats (default), history
+//                          or learned:<file>, which learns from the best moves of a test file first
+//   --victor               prove positions with the rules of victor before searching them, see src/victor.rs
+//
+// exits with status 1 if a regression against the baseline was found
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use connect4_rustbot::bench;
+use connect4_rustbot::ordering;
+use connect4_rustbot::Solver;
+use connect4_rustbot::cli::{fail, test_file, test_files};
+
+fn main() {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut limit = usize::MAX;
+    let mut format = String::from("text");
+    let mut baseline = None;
+    let mut threshold = 10.0;
+    let mut ordering = String::from("threats");
+    let mut victor = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(&format!("{arg} needs a value")));
+        match arg.as_str() {
+            "--limit" => limit = value().parse().unwrap_or_else(|_| fail("--limit needs a number")),
+            "--format" => format = value(),
+            "--baseline" => baseline = Some(value()),
+            "--threshold" => threshold = value().parse().unwrap_or_else(|_| fail("--threshold needs a number")),
+            "--ordering" => ordering = value(),
+            "--victor" => victor = true,
+            name if name.starts_with("--") => fail(&format!("unknown option {name}")),
+            name => {
+                files.push(test_file(name));
+            },
+        }
+    }
+
+    if !["text", "json", "csv"].contains(&format.as_str()) { fail(&format!("unknown format {format}")) }
+    let orderer = ordering::by_name(&ordering).unwrap_or_else(|e| fail(&e));
+
+    if files.is_empty() {
+        files = test_files().unwrap_or_else(|e| fail(&format!("Error reading test_files: {e}")));
+    }
+
+    let mut summaries = Vec::new();
+    for file in &files {
+        let cases = bench::read_test_file(file).unwrap_or_else(|e| fail(&format!("Error reading {}: {e}", file.display())));
+        let name = file.file_stem().unwrap().to_string_lossy();
+        let cases = &cases[..limit.min(cases.len())];
+        let summary = bench::run(&name, cases, || {
+            let mut solver = Solver::new();
+            solver.set_move_orderer(orderer());
+            solver.set_victor(victor);
+            solver
+        }).unwrap_or_else(|e| fail(&e));
+
+        // text results are printed as they come in, since the harder files take a while
+        if format == "text" { print!("{}", bench::to_text(std::slice::from_ref(&summary))) }
+        summaries.push(summary);
+    }
+
+    match format.as_str() {
+        "json" => print!("{}", bench::to_json(&summaries)),
+        "csv" => print!("{}", bench::to_csv(&summaries)),
+        _ => (),
+    }
+
+    if let Some(baseline) = baseline {
+        let contents = fs::read_to_string(&baseline).unwrap_or_else(|e| fail(&format!("Error reading {baseline}: {e}")));
+        let baseline = bench::parse_baseline(&contents).unwrap_or_else(|e| fail(&format!("Invalid baseline: {e}")));
+        let found = bench::regressions(&summaries, &baseline, threshold);
+        for regression in &found {
+            eprintln!("REGRESSION {regression}");
+        }
+        if !found.is_empty() { process::exit(1) }
+    }
+}
@@ -0,0 +1,41 @@
+// a drop-in replacement for Pascal Pons' c4solver, see src/pons.rs. reads positions from stdin, one
+// per line, and prints each line followed by its score
+//
+// usage: c4solver [options]
+//   -w          only print whether the position is a win (1), draw (0) or loss (-1)
+//   -a          print the score of every column instead, -1000 for full columns
+//   -b <file>   opening book to use (default: 7x6.book)
+
+use std::env;
+use std::io;
+use std::sync::Arc;
+use connect4_rustbot::Solver;
+use connect4_rustbot::pons::{self, Book};
+use connect4_rustbot::cli::fail;
+
+fn main() {
+    let mut weak = false;
+    let mut analyze = false;
+    let mut book_path = String::from("7x6.book");
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-w" => weak = true,
+            "-a" => analyze = true,
+            "-b" => book_path = args.next().unwrap_or_else(|| fail("-b needs a file")),
+            other => fail(&format!("unknown option {other}")),
+        }
+    }
+
+    // like Pons' solver, a missing book is reported but everything is still solved
+    let mut solver = Solver::new();
+    match Book::read(&book_path) {
+        Ok(book) => solver.set_book(Some(Arc::new(book))),
+        Err(e) => eprintln!("Unable to load opening book {book_path}: {e}"),
+    }
+
+    if let Err(e) = pons::run(io::stdin().lock(), io::stdout().lock(), io::stderr(), &mut solver, weak, analyze) {
+        fail(&format!("Error: {e}"));
+    }
+}
@@ -0,0 +1,37 @@
+// proves the score of a position with a certificate anyone can check, see src/certificate.rs
+//
+// usage:
+//   certificate prove <moves> <file>   solves the position and writes a certificate of its score
+//   certificate check <file>           checks a certificate, only using the rules of the game
+//
+// check exits with status 1 if the certificate does not prove its score
+
+use std::env;
+use std::process;
+use std::time::Instant;
+use connect4_rustbot::certificate::{self, Certificate};
+use connect4_rustbot::Solver;
+use connect4_rustbot::cli::fail;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<&str>>()[..] {
+        ["prove", moves, file] => {
+            let start = Instant::now();
+            let certificate = Certificate::prove(&mut Solver::new(), moves).unwrap_or_else(|e| fail(&format!("Invalid position {moves}: {e}")));
+            certificate.write(file).unwrap_or_else(|e| fail(&format!("Error writing {file}: {e}")));
+            println!("{moves} scores {}, proven with {} nodes in {:.3} seconds", certificate.score, certificate.nodes.len(), start.elapsed().as_secs_f64());
+        },
+        ["check", file] => {
+            let certificate = Certificate::read(file).unwrap_or_else(|e| fail(&format!("Error reading {file}: {e}")));
+            match certificate::check(&certificate) {
+                Ok(()) => println!("{} scores {}, the certificate is valid", certificate.moves, certificate.score),
+                Err(problem) => {
+                    eprintln!("the certificate is not valid: {problem}");
+                    process::exit(1);
+                },
+            }
+        },
+        _ => fail("usage: certificate prove <moves> <file> | certificate check <file>"),
+    }
+}
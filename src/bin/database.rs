@@ -0,0 +1,30 @@
+// builds a memory mapped database of solved positions (see src/database.rs) from files in the
+// "moves score" format of the test files, like the ones the generate binary writes
+//
+// usage: database <output file> <files...>
+//   files   paths, or names in test_files/ like End-Easy
+
+use std::env;
+use connect4_rustbot::{bench, Position};
+use connect4_rustbot::database::DatabaseBuilder;
+use connect4_rustbot::cli::{fail, test_file};
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 { fail("usage: database <output file> <files...>") }
+
+    let mut builder = DatabaseBuilder::new();
+    let mut added = 0;
+    for name in &args[1..] {
+        let path = test_file(name);
+        let cases = bench::read_test_file(&path).unwrap_or_else(|e| fail(&format!("Error reading {}: {e}", path.display())));
+        for case in &cases {
+            let pos = Position::from_moves(&case.moves).unwrap_or_else(|e| fail(&format!("{}: invalid position {}: {e}", path.display(), case.moves)));
+            builder.add(&pos, case.score);
+        }
+        added += cases.len();
+    }
+
+    builder.write(&args[0]).unwrap_or_else(|e| fail(&format!("Error writing {}: {e}", args[0])));
+    println!("wrote {added} positions to {}", args[0]);
+}
@@ -0,0 +1,49 @@
+// checks the solver against a local copy of connect-4.data from the UCI machine learning repository
+// (https://archive.ics.uci.edu/dataset/26/connect+4), which labels every position after 8 moves
+// with its outcome. the positions are early in the game, so solving all 67,557 takes a long time
+//
+// usage: dataset <path to connect-4.data> [options]
+//   --threads <n>   how many positions to solve at once (default: the number of cores)
+//   --sample <n>    only check every nth position
+//
+// exits with status 1 and lists the positions with the wrong label if there were any
+
+use std::env;
+use std::process;
+use std::thread;
+use std::time::Instant;
+use connect4_rustbot::dataset::{self, Entry};
+use connect4_rustbot::cli::fail;
+
+fn main() {
+    let mut path = None;
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut every = 1;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(&format!("{arg} needs a value")));
+        match arg.as_str() {
+            "--threads" => threads = value().parse().unwrap_or_else(|_| fail("--threads needs a number")),
+            "--sample" => every = value().parse().unwrap_or_else(|_| fail("--sample needs a number")),
+            name if name.starts_with("--") => fail(&format!("unknown option {name}")),
+            name => path = Some(name.to_string()),
+        }
+    }
+    let Some(path) = path else { fail("usage: dataset <path to connect-4.data> [--threads n] [--sample n]") };
+
+    let entries = dataset::read_file(&path).unwrap_or_else(|e| fail(&format!("Error reading {path}: {e}")));
+    let entries: Vec<(usize, &Entry)> = entries.iter().enumerate().step_by(every.max(1)).map(|(i, e)| (i + 1, e)).collect();
+
+    let start = Instant::now();
+    let wrong = dataset::verify(&entries, threads);
+    println!("{} of {} labels correct, {:.1} seconds", entries.len() - wrong.len(), entries.len(), start.elapsed().as_secs_f64());
+
+    if !wrong.is_empty() {
+        for (line, label, solved) in &wrong {
+            let moves = &entries.iter().find(|(l, _)| l == line).unwrap().1.moves;
+            eprintln!("  line {line}: {moves} labelled {label:?} but solved as {solved:?}");
+        }
+        process::exit(1);
+    }
+}
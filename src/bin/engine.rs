@@ -0,0 +1,6 @@
+// runs the engine protocol described in src/engine.rs on stdin and stdout
+use std::io;
+
+fn main() {
+    connect4_rustbot::engine::run(io::stdin().lock(), io::stdout());
+}
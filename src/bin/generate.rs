@@ -0,0 +1,86 @@
+// generates a new set of benchmark positions, solved and sorted into Easy, Medium and Hard files
+// like the ones in test_files
+//
+// usage: generate [options]
+//   --count <n>            positions to generate (default 1000)
+//   --plies <min>-<max>    how many moves into the game the positions are (default 20-30)
+//   --name <name>          files are written to <dir>/<name>-<bucket>.txt (default Generated)
+//   --dir <dir>            (default generated, so bench and verify do not pick them up from test_files)
+//   --seed <n>             makes the positions reproducible
+//   --timeout <ms>         skips positions that take longer than this to solve
+//   --easy <n>             positions searched below which a position is easy (default 10000)
+//   --medium <n>           and below which it is medium (default 1000000)
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use connect4_rustbot::generator::{Attempt, Bucket, Generator};
+use connect4_rustbot::cli::fail;
+
+fn main() {
+    let mut count = 1000;
+    let mut plies = 20..=30;
+    let mut name = String::from("Generated");
+    let mut dir = PathBuf::from("generated");
+    let mut seed = None;
+    let mut timeout = None;
+    let mut easy_limit = None;
+    let mut medium_limit = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(&format!("{arg} needs a value")));
+        let number = |value: String| value.parse::<u64>().unwrap_or_else(|_| fail(&format!("{arg} needs a number")));
+        match arg.as_str() {
+            "--count" => count = number(value()),
+            "--plies" => {
+                let range = value();
+                let (min, max) = range.split_once('-').unwrap_or((&range, &range));
+                match (min.parse(), max.parse()) {
+                    (Ok(min), Ok(max)) if min <= max && max <= 41 => plies = min..=max,
+                    _ => fail("--plies needs a range like 20-30, with at most 41 moves"),
+                }
+            },
+            "--name" => name = value(),
+            "--dir" => dir = PathBuf::from(value()),
+            "--seed" => seed = Some(number(value())),
+            "--timeout" => timeout = Some(Duration::from_millis(number(value()))),
+            "--easy" => easy_limit = Some(number(value())),
+            "--medium" => medium_limit = Some(number(value())),
+            _ => fail(&format!("unknown option {arg}")),
+        }
+    }
+
+    let mut generator = Generator::new(plies, seed);
+    generator.timeout = timeout;
+    if let Some(limit) = easy_limit { generator.easy_limit = limit }
+    if let Some(limit) = medium_limit { generator.medium_limit = limit }
+
+    let buckets = [Bucket::Easy, Bucket::Medium, Bucket::Hard];
+    let mut lines = [String::new(), String::new(), String::new()];
+    let mut skipped = 0;
+    for _ in 0..count {
+        match generator.generate() {
+            Attempt::Solved(generated) => {
+                let i = buckets.iter().position(|&b| b == generated.bucket).unwrap();
+                lines[i] += &format!("{generated}\n");
+            },
+            Attempt::TimedOut => skipped += 1,
+            Attempt::UsedUp => {
+                println!("stopped early, there are no new positions left with {}-{} moves", generator.plies.start(), generator.plies.end());
+                break
+            },
+        }
+    }
+
+    fs::create_dir_all(&dir).unwrap_or_else(|e| fail(&format!("Error creating {}: {e}", dir.display())));
+
+    for (bucket, lines) in buckets.iter().zip(&lines) {
+        if lines.is_empty() { continue }
+        let path = dir.join(format!("{name}-{bucket}.txt"));
+        fs::write(&path, lines).unwrap_or_else(|e| fail(&format!("Error writing {}: {e}", path.display())));
+        println!("{}: {} positions", path.display(), lines.lines().count());
+    }
+    if skipped > 0 { println!("skipped {skipped} positions that took too long to solve") }
+}
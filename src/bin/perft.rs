@@ -0,0 +1,48 @@
+// counts the move sequences and positions at each ply, and checks them against the known counts
+// when starting from the empty board
+//
+// usage: perft [options]
+//   --depth <n>      how many moves to count up to (default 8)
+//   --moves <moves>  the moves to the starting position, numbered 1-7 (default: the empty board)
+//
+// exits with status 1 if a count from the empty board does not match
+
+use std::env;
+use std::process;
+use std::time::Instant;
+use connect4_rustbot::perft::{perft, KNOWN_POSITIONS};
+use connect4_rustbot::Position;
+use connect4_rustbot::cli::fail;
+
+fn main() {
+    let mut depth = 8;
+    let mut moves = String::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(&format!("{arg} needs a value")));
+        match arg.as_str() {
+            "--depth" => depth = value().parse().unwrap_or_else(|_| fail("--depth needs a number")),
+            "--moves" => moves = value(),
+            _ => fail(&format!("unknown option {arg}")),
+        }
+    }
+    let start = Position::from_moves(&moves).unwrap_or_else(|e| fail(&format!("invalid moves: {e}")));
+
+    let timer = Instant::now();
+    let counts = perft(&start, depth);
+    let mut wrong = 0;
+    println!("{:>4} {:>16} {:>16} {:>16}", "ply", "sequences", "positions", "folded");
+    for c in &counts {
+        let check = match KNOWN_POSITIONS.get(start.num_moves() + c.ply) {
+            Some(&known) if moves.is_empty() && known != c.positions => {
+                wrong += 1;
+                format!("  expected {known}")
+            },
+            _ => String::new(),
+        };
+        println!("{:>4} {:>16} {:>16} {:>16}{check}", c.ply, c.sequences, c.positions, c.folded);
+    }
+    println!("{:.1} seconds", timer.elapsed().as_secs_f64());
+    if wrong > 0 { process::exit(1) }
+}
@@ -0,0 +1,44 @@
+// runs the http api described in src/server.rs, on 127.0.0.1:8080 unless given another address.
+// it is only built with --features server
+//
+// usage: server [address] [options]
+//   --table <file>      starts with a transposition table saved earlier, see src/table.rs
+//   --database <file>   looks positions up in a database of solved positions, see src/database.rs
+//   --tablebase <file>  looks late positions up in an endgame tablebase, see src/tablebase.rs
+use std::env;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use connect4_rustbot::{server, Solver};
+use connect4_rustbot::database::Database;
+use connect4_rustbot::cli::fail;
+
+fn main() {
+    let mut addr = String::from("127.0.0.1:8080");
+    let mut solver = Solver::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--table" => {
+                let path = args.next().unwrap_or_else(|| fail("--table needs a file"));
+                solver.load_table(&path).unwrap_or_else(|e| fail(&format!("Error loading {path}: {e}")));
+            },
+            "--database" => {
+                let path = args.next().unwrap_or_else(|| fail("--database needs a file"));
+                let database = Database::open(&path).unwrap_or_else(|e| fail(&format!("Error opening {path}: {e}")));
+                solver.set_database(Some(Arc::new(database)));
+            },
+            "--tablebase" => {
+                let path = args.next().unwrap_or_else(|| fail("--tablebase needs a file"));
+                let tablebase = Database::open(&path).unwrap_or_else(|e| fail(&format!("Error opening {path}: {e}")));
+                solver.set_tablebase(Some(Arc::new(tablebase)));
+            },
+            name if name.starts_with("--") => fail(&format!("unknown option {name}")),
+            name => addr = name.to_string(),
+        }
+    }
+
+    let listener = TcpListener::bind(&addr).unwrap_or_else(|e| fail(&format!("Error binding {addr}: {e}")));
+    println!("listening on http://{addr}");
+    server::serve(listener, Arc::new(Mutex::new(solver)));
+}
@@ -0,0 +1,128 @@
+// builds and uses a strong solution of the opening, see src/strong.rs
+//
+// usage:
+//   strong build <dir> --plies <n> [--root <moves>] [--shard-size <n>]
+//       solves every position up to n moves after the root (the empty board by default), skipping
+//       shards that are already finished. stopping and running the same command again carries on
+//   strong query <dir or database file> <moves...>
+//       prints the stored score of each position, or "none" if it is not stored
+//   strong merge <dir> <output file>
+//       merges every finished shard of a job into one database file
+//   strong verify <dir> [--sample <n>]
+//       checks every finished shard holds the positions it should, solving every nth again (default 100)
+//
+// verify exits with status 1 and lists the problems if there were any
+
+use std::env;
+use std::path::Path;
+use std::process;
+use std::time::Instant;
+use connect4_rustbot::database::{Database, DatabaseBuilder};
+use connect4_rustbot::strong::Job;
+use connect4_rustbot::Position;
+use connect4_rustbot::cli::fail;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let usage = "usage: strong build|query|merge|verify <dir> ...";
+    if args.len() < 2 { fail(usage) }
+    let rest = &args[2..];
+    match args[0].as_str() {
+        "build" => build(&args[1], rest),
+        "query" => query(&args[1], rest),
+        "merge" => merge(&args[1], rest),
+        "verify" => verify(&args[1], rest),
+        _ => fail(usage),
+    }
+}
+
+fn build(dir: &str, args: &[String]) {
+    let mut plies = None;
+    let mut root = String::new();
+    let mut shard_size = 10_000;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(&format!("{arg} needs a value")));
+        match arg.as_str() {
+            "--plies" => plies = Some(value().parse().unwrap_or_else(|_| fail("--plies needs a number"))),
+            "--root" => root = value().clone(),
+            "--shard-size" => shard_size = value().parse().unwrap_or_else(|_| fail("--shard-size needs a number")),
+            other => fail(&format!("unknown option {other}")),
+        }
+    }
+    let Some(plies) = plies else { fail("build needs --plies") };
+
+    let job = Job::create(dir, &root, plies, shard_size).unwrap_or_else(|e| fail(&format!("Error starting job in {dir}: {e}")));
+    let start = Instant::now();
+    let result = job.run(|ply, shard, shards| {
+        println!("ply {ply}: shard {} of {shards} done, {:.1} seconds", shard + 1, start.elapsed().as_secs_f64());
+        true
+    });
+    if let Err(e) = result { fail(&format!("Error: {e}")) }
+}
+
+fn query(path: &str, moves: &[String]) {
+    let databases: Vec<Database> = if Path::new(path).is_dir() {
+        open_job(path).shards().iter().map(|shard| open(&shard.display().to_string())).collect()
+    } else {
+        vec![open(path)]
+    };
+
+    for moves in moves {
+        match Position::from_moves(moves) {
+            Ok(pos) => match databases.iter().find_map(|database| database.get(&pos)) {
+                Some(score) => println!("{moves} {score}"),
+                None => println!("{moves} none"),
+            },
+            Err(e) => eprintln!("{moves}: {e}"),
+        }
+    }
+}
+
+fn merge(dir: &str, args: &[String]) {
+    let [output] = args else { fail("usage: strong merge <dir> <output file>") };
+    let mut builder = DatabaseBuilder::new();
+    let shards = open_job(dir).shards();
+    for shard in &shards {
+        builder.extend_from(&open(&shard.display().to_string()));
+    }
+    builder.write(output).unwrap_or_else(|e| fail(&format!("Error writing {output}: {e}")));
+    println!("merged {} shards into {output}", shards.len());
+}
+
+fn verify(dir: &str, args: &[String]) {
+    let every = match args {
+        [] => 100,
+        [option, n] if option == "--sample" => n.parse().unwrap_or_else(|_| fail("--sample needs a number")),
+        _ => fail("usage: strong verify <dir> [--sample n]"),
+    };
+
+    let job = open_job(dir);
+    let mut problems = Vec::new();
+    let mut checked = 0;
+    for ply in 0..=job.plies {
+        let mut shard = 0;
+        while job.shard_path(ply, shard).exists() {
+            problems.extend(job.verify_shard(ply, shard, every).unwrap_or_else(|e| fail(&format!("Error reading ply {ply} shard {shard}: {e}"))));
+            checked += 1;
+            shard += 1;
+        }
+    }
+
+    println!("checked {checked} shards, {} problems", problems.len());
+    if !problems.is_empty() {
+        for problem in &problems {
+            eprintln!("  {problem}");
+        }
+        process::exit(1);
+    }
+}
+
+fn open_job(dir: &str) -> Job {
+    Job::open(dir).unwrap_or_else(|e| fail(&format!("Error opening job in {dir}: {e}")))
+}
+
+fn open(path: &str) -> Database {
+    Database::open(path).unwrap_or_else(|e| fail(&format!("Error opening {path}: {e}")))
+}
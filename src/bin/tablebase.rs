@@ -0,0 +1,66 @@
+// generates and checks endgame tablebases, see src/tablebase.rs
+//
+// usage:
+//   tablebase build <output file> --root <moves> --stones <n>
+//       scores every position with at least n stones after the root. a tablebase covers the subtree
+//       of one root, which can have at most 24 empty spots (see tablebase::MAX_EMPTY)
+//   tablebase verify <file> --root <moves> [--sample <n>]
+//       solves every nth position again with a regular search (default 100), the root must be the
+//       one the tablebase was built from
+//
+// verify exits with status 1 and lists the problems if there were any
+
+use std::env;
+use std::process;
+use std::time::Instant;
+use connect4_rustbot::database::Database;
+use connect4_rustbot::{tablebase, Position};
+use connect4_rustbot::cli::fail;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let usage = "usage: tablebase build|verify <file> [options]";
+    if args.len() < 2 { fail(usage) }
+
+    let mut root = None;
+    let mut stones = None;
+    let mut every = 100;
+    let mut options = args[2..].iter();
+    while let Some(arg) = options.next() {
+        let mut value = || options.next().unwrap_or_else(|| fail(&format!("{arg} needs a value")));
+        match arg.as_str() {
+            "--root" => root = Some(value().clone()),
+            "--stones" => stones = Some(value().parse().unwrap_or_else(|_| fail("--stones needs a number"))),
+            "--sample" => every = value().parse().unwrap_or_else(|_| fail("--sample needs a number")),
+            other => fail(&format!("unknown option {other}")),
+        }
+    }
+    let Some(root) = root else { fail("--root is needed, a tablebase covers the positions after it") };
+    let root = Position::from_moves(&root).unwrap_or_else(|e| fail(&format!("invalid root position: {e}")));
+    if 42 - root.num_moves() > tablebase::MAX_EMPTY {
+        fail(&format!("the root has {} empty spots, at most {} fit in memory", 42 - root.num_moves(), tablebase::MAX_EMPTY))
+    }
+    let path = &args[1];
+    let start = Instant::now();
+
+    match args[0].as_str() {
+        "build" => {
+            let Some(stones) = stones else { fail("build needs --stones") };
+            tablebase::generate(&root, stones).write(path).unwrap_or_else(|e| fail(&format!("Error writing {path}: {e}")));
+            let written = Database::open(path).unwrap_or_else(|e| fail(&format!("Error reading {path}: {e}")));
+            println!("{} positions, {:.1} seconds", written.len(), start.elapsed().as_secs_f64());
+        },
+        "verify" => {
+            let tablebase = Database::open(path).unwrap_or_else(|e| fail(&format!("Error reading {path}: {e}")));
+            let problems = tablebase::verify(&tablebase, &root, every);
+            println!("{} problems, {:.1} seconds", problems.len(), start.elapsed().as_secs_f64());
+            if !problems.is_empty() {
+                for problem in &problems {
+                    eprintln!("  {problem}");
+                }
+                process::exit(1);
+            }
+        },
+        _ => fail(usage),
+    }
+}
@@ -0,0 +1,59 @@
+// checks that the solver gets every position in the test files right
+//
+// usage: verify [options] [files...]
+//   files              test file paths, or names in test_files/ like End-Easy (all of test_files/ by default)
+//   --threads <n>      how many positions to solve at once (default: the number of cores)
+//   --sample <n>       only check every nth position of each file
+//
+// exits with status 1 and lists the positions that were wrong if there were any
+
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Instant;
+use connect4_rustbot::{bench, verify};
+use connect4_rustbot::cli::{fail, test_file, test_files};
+
+fn main() {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut every = 1;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| fail(&format!("{arg} needs a value")));
+        match arg.as_str() {
+            "--threads" => threads = value().parse().unwrap_or_else(|_| fail("--threads needs a number")),
+            "--sample" => every = value().parse().unwrap_or_else(|_| fail("--sample needs a number")),
+            name if name.starts_with("--") => fail(&format!("unknown option {name}")),
+            name => {
+                files.push(test_file(name));
+            },
+        }
+    }
+
+    if files.is_empty() {
+        files = test_files().unwrap_or_else(|e| fail(&format!("Error reading test_files: {e}")));
+    }
+
+    let mut checked = 0;
+    let mut mismatches = Vec::new();
+    for file in &files {
+        let cases = bench::read_test_file(file).unwrap_or_else(|e| fail(&format!("Error reading {}: {e}", file.display())));
+        let name = file.file_stem().unwrap().to_string_lossy();
+        let cases = verify::sample(&cases, every);
+
+        let start = Instant::now();
+        let found = verify::verify(&name, &cases, threads);
+        println!("{name}: {} of {} correct, {:.1} seconds", cases.len() - found.len(), cases.len(), start.elapsed().as_secs_f64());
+
+        checked += cases.len();
+        mismatches.extend(found);
+    }
+
+    if let Err(report) = verify::summary(checked, &mismatches) {
+        eprint!("{report}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,345 @@
+// certificates that prove the score of a position, so a result can be published and checked by
+// someone who does not trust the solver. a score s is proven by two game trees, one showing the
+// player to play gets at least s and one showing they get at most s:
+//   at least v   one move that wins straight away with a score of at least v, or one move after
+//                which the opponent gets at most -v
+//   at most v    for every move: it wins straight away with a score of at most v, or after it the
+//                opponent gets at least -v
+// a bound needs no moves when every game from the position ends within it, like at least v when
+// losing to the opponent's next stone still scores v. positions reached in more than one order
+// share a node, so the trees are stored as one graph of nodes.
+//
+// a certificate file is little endian:
+//   bytes 0-3     "C4PC"
+//   bytes 4-7     format version
+//   byte 8        # of moves of the position, followed by the moves numbered 1-7 as ascii
+//   then          the score as an i8, # of nodes as a u32, and the nodes of the at least and at
+//                 most trees of the position as u32s
+//   then          the nodes, each an at least flag (1 for at least, 0 for at most) and the bound as
+//                 an i8, # of moves as a u8, and for every move its column (0-6) as a u8 and its
+//                 node as a u32, or u32::MAX for a move that wins straight away
+//   then          the fnv-1a hash of everything before it
+//
+// check only uses the rules of the game (make_move, is_legal_move and is_winning_move), so it does
+// not depend on anything the solver does
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::{Position, Solver};
+
+const MAGIC: &[u8; 4] = b"C4PC";
+pub const VERSION: u32 = 1;
+
+// the node of a move that wins straight away
+pub const WIN: u32 = u32::MAX;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Node {
+    pub at_least: bool, // whether the player to play gets at least the bound, or at most
+    pub bound: i8,
+    pub moves: Vec<(u8, u32)>, // columns and the nodes of the positions after them
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Certificate {
+    pub moves: String, // the position, numbered 1-7
+    pub score: i8,
+    pub nodes: Vec<Node>,
+    pub roots: [u32; 2], // the at least and at most nodes of the position
+}
+
+impl Certificate {
+
+    // solves a position and proves its score, an error if the moves are not a valid position
+    pub fn prove(solver: &mut Solver, moves: &str) -> Result<Certificate, String> {
+        let mut pos = Position::from_moves(moves)?;
+        let (score, _) = solver.score(&mut pos).unwrap();
+        let mut builder = Builder { solver, nodes: Vec::new(), index: HashMap::new() };
+        let roots = [builder.at_least(&mut pos, score), builder.at_most(&mut pos, score)];
+        Ok(Certificate { moves: moves.to_string(), score, nodes: builder.nodes, roots })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.push(self.moves.len() as u8);
+        bytes.extend_from_slice(self.moves.as_bytes());
+        bytes.push(self.score as u8);
+        bytes.extend_from_slice(&(self.nodes.len() as u32).to_le_bytes());
+        for root in self.roots {
+            bytes.extend_from_slice(&root.to_le_bytes());
+        }
+        for node in &self.nodes {
+            bytes.extend_from_slice(&[node.at_least as u8, node.bound as u8, node.moves.len() as u8]);
+            for &(col, child) in &node.moves {
+                bytes.push(col);
+                bytes.extend_from_slice(&child.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(&checksum(&bytes).to_le_bytes());
+        bytes
+    }
+
+    // reads a certificate written by to_bytes, without checking it proves anything
+    pub fn from_bytes(bytes: &[u8]) -> Result<Certificate, String> {
+        if bytes.len() < 17 || &bytes[..4] != MAGIC { return Err(String::from("not a certificate file")) }
+        let (body, stored) = bytes.split_at(bytes.len() - 8);
+        if checksum(body).to_le_bytes() != stored { return Err(String::from("certificate file is corrupted, the checksum does not match")) }
+        let version = u32::from_le_bytes(body[4..8].try_into().unwrap());
+        if version != VERSION { return Err(format!("certificate is format version {version}, expected {VERSION}")) }
+
+        let mut reader = Reader { bytes: body, at: 8 };
+        let len = reader.u8()? as usize;
+        let moves = String::from_utf8(reader.take(len)?.to_vec()).map_err(|_| String::from("the moves are not text"))?;
+        let score = reader.u8()? as i8;
+        let count = reader.u32()?;
+        let roots = [reader.u32()?, reader.u32()?];
+        let mut nodes = Vec::new();
+        for _ in 0..count {
+            let at_least = reader.u8()? == 1;
+            let bound = reader.u8()? as i8;
+            let len = reader.u8()?;
+            let moves = (0..len).map(|_| Ok((reader.u8()?, reader.u32()?))).collect::<Result<Vec<(u8, u32)>, String>>()?;
+            nodes.push(Node { at_least, bound, moves });
+        }
+        if reader.at != body.len() { return Err(String::from("certificate file has bytes after the last node")) }
+        Ok(Certificate { moves, score, nodes, roots })
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, file_path: P) -> io::Result<()> {
+        fs::write(file_path, self.to_bytes())
+    }
+
+    pub fn read<P: AsRef<Path>>(file_path: P) -> io::Result<Certificate> {
+        Certificate::from_bytes(&fs::read(file_path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        let taken = self.bytes.get(self.at..self.at + len).ok_or_else(|| String::from("certificate file is truncated"))?;
+        self.at += len;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+struct Builder<'a> {
+    solver: &'a mut Solver,
+    nodes: Vec<Node>,
+    index: HashMap<(u64, bool, i8), u32>, // nodes already made for a position and bound
+}
+
+impl Builder<'_> {
+    fn at_least(&mut self, pos: &mut Position, bound: i8) -> u32 {
+        if let Some(&node) = self.index.get(&(pos.key(), true, bound)) { return node }
+        let moves = if bound <= lowest(pos) {
+            Vec::new()
+        } else if let Some(col) = (0..7).find(|&col| pos.is_legal_move(col) && pos.is_winning_move(col)) {
+            vec![(col as u8, WIN)]
+        } else {
+            // the move to the position the opponent does worst in, proven with a null window search
+            let col = [3, 2, 4, 1, 5, 0, 6].into_iter().find(|&col| {
+                if !pos.is_legal_move(col) { return false }
+                pos.make_move(col);
+                let (score, _) = self.solver.search(pos, -bound, -bound + 1).unwrap();
+                pos.undo_move();
+                score <= -bound
+            }).expect("the position is not as good as its score");
+            pos.make_move(col);
+            let child = self.at_most(pos, -bound);
+            pos.undo_move();
+            vec![(col as u8, child)]
+        };
+        self.add(pos, Node { at_least: true, bound, moves })
+    }
+
+    fn at_most(&mut self, pos: &mut Position, bound: i8) -> u32 {
+        if let Some(&node) = self.index.get(&(pos.key(), false, bound)) { return node }
+        let mut moves = Vec::new();
+        if bound < highest(pos) {
+            for col in (0..7).filter(|&col| pos.is_legal_move(col)).collect::<Vec<usize>>() {
+                assert!(!pos.is_winning_move(col), "the position is better than its score");
+                pos.make_move(col);
+                moves.push((col as u8, self.at_least(pos, -bound)));
+                pos.undo_move();
+            }
+        }
+        self.add(pos, Node { at_least: false, bound, moves })
+    }
+
+    fn add(&mut self, pos: &Position, node: Node) -> u32 {
+        let index = self.nodes.len() as u32;
+        self.index.insert((pos.key(), node.at_least, node.bound), index);
+        self.nodes.push(node);
+        index
+    }
+}
+
+// the worst score the player to play can get, losing to the opponent's next stone
+fn lowest(pos: &Position) -> i8 {
+    -(((42 - pos.num_moves()) / 2) as i8)
+}
+
+// the best score the player to play can get, winning with their next stone
+fn highest(pos: &Position) -> i8 {
+    ((43 - pos.num_moves()) / 2) as i8
+}
+
+// checks a certificate proves its score, returning the first problem found
+pub fn check(certificate: &Certificate) -> Result<(), String> {
+    let mut pos = Position::from_moves(&certificate.moves)?;
+    let mut checker = Checker { nodes: &certificate.nodes, checked: HashSet::new() };
+    checker.check(&mut pos, certificate.roots[0], true, certificate.score)?;
+    checker.check(&mut pos, certificate.roots[1], false, certificate.score)
+}
+
+struct Checker<'a> {
+    nodes: &'a [Node],
+    checked: HashSet<(u32, u64)>, // nodes already checked for a position
+}
+
+impl Checker<'_> {
+    // checks that a node proves a bound for the position
+    fn check(&mut self, pos: &mut Position, index: u32, at_least: bool, bound: i8) -> Result<(), String> {
+        let moves: String = pos.moves.iter().map(|&col| char::from_digit(col as u32 + 1, 10).unwrap()).collect();
+        let fail = |problem: &str| Err(format!("{problem} after \"{moves}\" (node {index})"));
+        let Some(node) = self.nodes.get(index as usize) else { return fail("missing node") };
+        let kind = if at_least { "at least" } else { "at most" };
+        if node.at_least != at_least || node.bound != bound { return fail(&format!("expected a node proving {kind} {bound}")) }
+        if !self.checked.insert((index, pos.key())) { return Ok(()) }
+
+        // the score a win this turn is worth, and the bounds every game from here ends within
+        let win = ((43 - pos.num_moves()) / 2) as i8;
+        if at_least && bound <= -(((42 - pos.num_moves()) / 2) as i8) { return Ok(()) }
+        if !at_least && bound >= win { return Ok(()) }
+        // a full board is a draw, which has no moves to check
+        if pos.num_moves() == 42 { return fail(&format!("the board is full, a draw is not {kind} {bound}")) }
+
+        if at_least {
+            let [(col, child)] = node.moves[..] else { return fail("expected one move") };
+            let col = col as usize;
+            if col >= 7 || !pos.is_legal_move(col) { return fail(&format!("column {} is not a legal move", col + 1)) }
+            if child == WIN {
+                return if pos.is_winning_move(col) { Ok(()) } else { fail(&format!("column {} does not win", col + 1)) }
+            }
+            pos.make_move(col);
+            let result = self.check(pos, child, false, -bound);
+            pos.undo_move();
+            result
+        } else {
+            let mut moves = node.moves.iter();
+            for col in (0..7).filter(|&col| pos.is_legal_move(col)).collect::<Vec<usize>>() {
+                if pos.is_winning_move(col) { return fail(&format!("column {} wins for {win}", col + 1)) }
+                let Some(&(node_col, child)) = moves.next() else { return fail(&format!("column {} is missing", col + 1)) };
+                if node_col as usize != col { return fail(&format!("expected column {}", col + 1)) }
+                pos.make_move(col);
+                let result = self.check(pos, child, true, -bound);
+                pos.undo_move();
+                result?;
+            }
+            if moves.next().is_some() { return fail("there are moves that are not legal") }
+            Ok(())
+        }
+    }
+}
+
+// 64 bit fnv-1a
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prove_and_check() {
+        let mut solver = Solver::new();
+        for moves in ["2252576253462244111563365343671351441", "7422341735647741166133573473242566", "274552224131661", "66513447136647211277114"] {
+            let certificate = Certificate::prove(&mut solver, moves).unwrap();
+            assert_eq!(certificate.score, solver.score(&mut Position::from_moves(moves).unwrap()).unwrap().0);
+            assert_eq!(check(&certificate), Ok(()), "{moves}");
+
+            let read = Certificate::from_bytes(&certificate.to_bytes()).unwrap();
+            assert_eq!(read, certificate);
+        }
+    }
+
+    #[test]
+    fn test_catches_wrong_certificates() {
+        let mut solver = Solver::new();
+        let certificate = Certificate::prove(&mut solver, "274552224131661").unwrap();
+
+        // claiming a better score
+        let mut better = certificate.clone();
+        better.score += 1;
+        assert!(check(&better).is_err());
+
+        // leaving out a reply of the opponent
+        let mut missing = certificate.clone();
+        let node = missing.nodes.iter_mut().find(|node| !node.at_least && node.moves.len() > 1).unwrap();
+        node.moves.pop();
+        assert!(check(&missing).is_err());
+
+        // playing a different move for the winner
+        let mut other = certificate.clone();
+        let node = other.nodes.iter_mut().find(|node| node.at_least && node.moves.len() == 1 && node.moves[0].1 != WIN).unwrap();
+        node.moves[0].0 = (node.moves[0].0 + 1) % 7;
+        assert!(check(&other).is_err());
+
+        let mut bytes = certificate.to_bytes();
+        bytes[20] ^= 1;
+        assert!(Certificate::from_bytes(&bytes).unwrap_err().contains("checksum"));
+        assert!(Certificate::from_bytes(b"not a certificate").is_err());
+    }
+
+    #[test]
+    fn test_full_board() {
+        // one move left, which draws
+        let moves = "71255763773133525731261364622167124446454";
+        let certificate = Certificate::prove(&mut Solver::new(), moves).unwrap();
+        assert_eq!(certificate.score, 0);
+        assert_eq!(check(&certificate), Ok(()));
+
+        // claiming a win by 1, with the opponent getting at most -1 once the board is full
+        let col = (0..7).find(|&col| Position::from_moves(moves).unwrap().is_legal_move(col)).unwrap() as u8;
+        let forged = Certificate {
+            moves: moves.to_string(),
+            score: 1,
+            nodes: vec![
+                Node { at_least: true, bound: 1, moves: vec![(col, 1)] },
+                Node { at_least: false, bound: -1, moves: Vec::new() },
+                Node { at_least: false, bound: 1, moves: Vec::new() },
+            ],
+            roots: [0, 2],
+        };
+        assert!(check(&forged).unwrap_err().contains("the board is full"));
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let path = std::env::temp_dir().join(format!("c4pc-test-{}", std::process::id()));
+        let certificate = Certificate::prove(&mut Solver::new(), "2252576253462244111563365343671351441").unwrap();
+        certificate.write(&path).unwrap();
+        let read = Certificate::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read, certificate);
+    }
+
+}
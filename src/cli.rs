@@ -0,0 +1,26 @@
+// helpers shared by the binaries in src/bin
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process;
+
+// prints the problem and exits with status 2, for bad arguments and files that can not be used
+pub fn fail(message: &str) -> ! {
+    eprintln!("{message}");
+    process::exit(2);
+}
+
+// a test file given as a path, or as a name in test_files/ like End-Easy
+pub fn test_file(name: &str) -> PathBuf {
+    let path = PathBuf::from(name);
+    if path.exists() { path } else { PathBuf::from(format!("test_files/{name}.txt")) }
+}
+
+// every test file in test_files/, sorted by name
+pub fn test_files() -> io::Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = fs::read_dir("test_files")?.filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt")).collect();
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,188 @@
+// cylinder connect 4, where the leftmost and rightmost columns are adjacent.
+// the regular bitboard shifts rely on the empty sentinel row to stop a line running from one column
+// into the next, but a shift can never carry a line from column 6 back around to column 0.
+// so here horizontal movement rotates the columns instead of shifting them, and vertical movement
+// is still a plain shift (the sentinel row keeps doing its job there)
+
+use crate::mirror;
+
+// every bit used by the 7 columns, sentinel row included
+const BOARD_MASK: u64 = (1 << 49) - 1;
+
+// every playable spot, so the 6 real rows of each column
+const PLAYABLE_MASK: u64 = 279258638311359;
+
+// moves every column n places to the right, wrapping the rightmost columns around to the left
+fn rotate(b: u64, n: u32) -> u64 {
+    let shift = 7 * (n % 7);
+    if shift == 0 { return b }
+    (b << shift | b >> (49 - shift)) & BOARD_MASK
+}
+
+// check if a bitboard contains a connect 4, with lines allowed to wrap around
+pub fn is_connect_four(b: u64) -> bool {
+    ((b & b << 1 & b << 2 & b << 3) |
+        (b & rotate(b, 1) & rotate(b, 2) & rotate(b, 3)) |
+        (b & rotate(b, 1) << 1 & rotate(b, 2) << 2 & rotate(b, 3) << 3) |
+        (b & rotate(b, 1) >> 1 & rotate(b, 2) >> 2 & rotate(b, 3) >> 3)) != 0
+}
+
+// gets threats of opponent, specifically where they are, with lines allowed to wrap around
+pub fn get_threats(board: [u64; 2], player: usize) -> u64 {
+    let open: u64 = !(board[0] | board[1]) & PLAYABLE_MASK;
+    let opp: u64 = board[player];
+
+    // opponent pieces moved n columns right (r) or left (l)
+    let r = |n: u32| rotate(opp, n);
+    let l = |n: u32| rotate(opp, 7 - n);
+
+    let mut threats: u64 = 0;
+
+    // vertical threats
+    threats |= open & opp << 1 & opp << 2 & opp << 3;
+
+    // horizontal threats
+    threats |= open & r(1) & r(2) & r(3);
+    threats |= l(1) & open & r(1) & r(2);
+    threats |= l(1) & l(2) & open & r(1);
+    threats |= l(1) & l(2) & l(3) & open;
+
+    // positive diagonol threats
+    threats |= open & r(1) << 1 & r(2) << 2 & r(3) << 3;
+    threats |= l(1) >> 1 & open & r(1) << 1 & r(2) << 2;
+    threats |= l(2) >> 2 & l(1) >> 1 & open & r(1) << 1;
+    threats |= l(3) >> 3 & l(2) >> 2 & l(1) >> 1 & open;
+
+    // negative diagonol threats
+    threats |= open & r(1) >> 1 & r(2) >> 2 & r(3) >> 3;
+    threats |= l(1) << 1 & open & r(1) >> 1 & r(2) >> 2;
+    threats |= l(2) << 2 & l(1) << 1 & open & r(1) >> 1;
+    threats |= l(3) << 3 & l(2) << 2 & l(1) << 1 & open;
+
+    threats
+}
+
+// get unique key for a position key. every rotation of a cylinder is the same position,
+// and so is every mirror image, so the lowest of those 14 keys is used
+pub fn hash(key: u64) -> u64 {
+    let mirrored = mirror(key);
+    let mut lowest = key.min(mirrored);
+    for n in 1..7 {
+        lowest = lowest.min(rotate(key, n)).min(rotate(mirrored, n));
+    }
+    lowest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Position, Rules};
+    use rand::prelude::*;
+
+    fn cylinder_position(moves: Vec<usize>) -> Position {
+        let mut p = Position::with_rules(Rules::Cylinder);
+        p.make_moves(moves);
+        p
+    }
+
+    // whether (col, row) belongs to the player, wrapping the column around
+    fn owns(b: u64, col: i32, row: i32) -> bool {
+        if !(0..6).contains(&row) { return false }
+        b >> (7 * col.rem_euclid(7) + row) & 1 == 1
+    }
+
+    // slow but obvious check of every line through every square
+    fn brute_force_connect_four(b: u64) -> bool {
+        for col in 0..7 {
+            for row in 0..6 {
+                for (dc, dr) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+                    if (0..4).all(|i| owns(b, col + dc * i, row + dr * i)) { return true }
+                }
+            }
+        }
+        false
+    }
+
+    // slow but obvious search for every empty square that completes a line
+    fn brute_force_threats(board: [u64; 2], player: usize) -> u64 {
+        let mut threats = 0;
+        for col in 0..7 {
+            for row in 0..6 {
+                let square = 1 << (7 * col + row);
+                if (board[0] | board[1]) & square != 0 { continue }
+                if brute_force_connect_four(board[player] | square) && !brute_force_connect_four(board[player]) {
+                    threats |= square;
+                }
+            }
+        }
+        threats
+    }
+
+    #[test]
+    fn test_is_winning_move_wraps_horizontally() {
+        let p = cylinder_position(vec![5, 5, 6, 6, 0, 0]);
+        assert!(p.is_winning_move(1));
+
+        let mut standard = Position::new();
+        standard.make_moves(vec![5, 5, 6, 6, 0, 0]);
+        assert!(!standard.is_winning_move(1));
+    }
+
+    #[test]
+    fn test_is_winning_move_wraps_diagonally() {
+        // first player builds 5-0, 6-1, 0-2 and wins on 1-3
+        let p = cylinder_position(vec![5, 6, 6, 0, 0, 1, 0, 1, 3, 1]);
+        assert!(p.is_winning_move(1));
+    }
+
+    #[test]
+    fn test_is_winning_move_does_not_wrap_vertically() {
+        let p = cylinder_position(vec![6, 6, 6, 0, 6, 0, 0, 4]);
+        assert!(!p.is_winning_move(0));
+    }
+
+    #[test]
+    fn test_threats_wrap() {
+        let p = cylinder_position(vec![5, 5, 6, 6, 0]);
+        let threats = get_threats(p.board, 1 - p.turn);
+        assert_eq!(threats, 1 << 7 | 1 << 28);
+    }
+
+    #[test]
+    fn test_against_brute_force() {
+        let mut rng = StdRng::seed_from_u64(26);
+        for _ in 0..200 {
+            let mut p = Position::with_rules(Rules::Cylinder);
+            for _ in 0..rng.gen_range(0..30) {
+                let legal: Vec<usize> = (0..7).filter(|&c| p.is_legal_move(c)).collect();
+                p.make_move(*legal.choose(&mut rng).unwrap());
+            }
+            for player in 0..2 {
+                assert_eq!(is_connect_four(p.board[player]), brute_force_connect_four(p.board[player]));
+                if !brute_force_connect_four(p.board[player]) {
+                    assert_eq!(get_threats(p.board, player), brute_force_threats(p.board, player));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_rotation() {
+        let moves = vec![0, 1, 1, 2, 6, 6, 3];
+        let p = cylinder_position(moves.clone());
+        for n in 1..7 {
+            let rotated = cylinder_position(moves.iter().map(|m| (m + n) % 7).collect());
+            assert_eq!(p.hash(), rotated.hash());
+        }
+        let mirrored = cylinder_position(moves.iter().map(|m| 6 - m).collect());
+        assert_eq!(p.hash(), mirrored.hash());
+    }
+
+    #[test]
+    fn test_score_wrapping_win() {
+        // the first player threatens both 1 and 4 around the back of the board
+        let mut p = cylinder_position(vec![5, 5, 6, 6, 0]);
+        let (s, _p) = crate::score(&mut p);
+        assert!(s < 0);
+    }
+}
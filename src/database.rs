@@ -0,0 +1,281 @@
+// a read-only database of solved positions that is memory mapped instead of loaded, so many server
+// processes can share one copy through the page cache and start without reading the whole file.
+// it is safe to look positions up from many threads at once.
+//
+// a database file is little endian:
+//   bytes 0-3     "C4DB"
+//   bytes 4-7     format version
+//   bytes 8-15    # of entries
+//   bytes 16-23   most moves played in any of the positions, so deeper positions are not looked up
+//   bytes 24-31   fewest moves played in any of the positions, so shallower ones are not looked up
+//   then 8 bytes per entry, sorted by Position::hash, which is held in bits 0-48. bits 56-63 hold the
+//   score for the player to play as an i8
+
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use memmap2::Mmap;
+use crate::{Position, Rules};
+
+const MAGIC: &[u8; 4] = b"C4DB";
+pub const VERSION: u32 = 2;
+const HEADER_LEN: usize = 32;
+const KEY_MASK: u64 = (1 << 49) - 1;
+
+pub struct Database {
+    map: Mmap,
+    len: usize,
+    min_moves: usize,
+    max_moves: usize,
+}
+
+impl Database {
+
+    // maps a database file, checking its header and size but not reading the entries
+    pub fn open<P: AsRef<Path>>(file_path: P) -> io::Result<Database> {
+        let file = File::open(file_path)?;
+        // safety: the file must not be modified or truncated while it is open. reading a truncated
+        // map faults with SIGBUS, and any change to the mapped bytes is undefined behaviour. the
+        // builder and the shard writers only ever write a temporary file and rename it over the
+        // database, which leaves a file that is already mapped alone
+        let map = unsafe { Mmap::map(&file)? };
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+
+        if map.len() < HEADER_LEN || &map[..4] != MAGIC { return Err(invalid(String::from("not a database file"))) }
+        let version = u32::from_le_bytes(map[4..8].try_into().unwrap());
+        if version != VERSION { return Err(invalid(format!("database is format version {version}, expected {VERSION}"))) }
+        let len = u64::from_le_bytes(map[8..16].try_into().unwrap()) as usize;
+        let max_moves = u64::from_le_bytes(map[16..24].try_into().unwrap()) as usize;
+        let min_moves = u64::from_le_bytes(map[24..32].try_into().unwrap()) as usize;
+        // the # of entries comes from the file, so a corrupt one must not overflow the size
+        let size = len.checked_mul(8).and_then(|n| n.checked_add(HEADER_LEN));
+        if size != Some(map.len()) { return Err(invalid(format!("database should hold {len} entries but is {} bytes", map.len()))) }
+
+        Ok(Database { map, len, min_moves, max_moves })
+    }
+
+    // # of positions in the database
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // fewest moves played in any of the positions
+    pub fn min_moves(&self) -> usize {
+        self.min_moves
+    }
+
+    // most moves played in any of the positions
+    pub fn max_moves(&self) -> usize {
+        self.max_moves
+    }
+
+    // every position's hash and score, in order of hash
+    pub fn iter(&self) -> impl Iterator<Item = (u64, i8)> + '_ {
+        (0..self.len).map(|i| self.entry(i)).map(|entry| (entry & KEY_MASK, (entry >> 56) as i8))
+    }
+
+    // score of a position for the player to play, none if it is not in the database
+    pub fn get(&self, pos: &Position) -> Option<i8> {
+        if pos.rules != Rules::Standard || !(self.min_moves..=self.max_moves).contains(&pos.num_moves()) { return None }
+        let hash = pos.hash();
+
+        // binary search, reading each entry straight out of the mapped file
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let entry = self.entry(mid);
+            match (entry & KEY_MASK).cmp(&hash) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Some((entry >> 56) as i8),
+            }
+        }
+        None
+    }
+
+    fn entry(&self, i: usize) -> u64 {
+        let start = HEADER_LEN + 8 * i;
+        u64::from_le_bytes(self.map[start..start + 8].try_into().unwrap())
+    }
+}
+
+// collects solved positions and writes them as a database
+pub struct DatabaseBuilder {
+    entries: Vec<u64>,
+    min_moves: usize,
+    max_moves: usize,
+}
+
+impl Default for DatabaseBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DatabaseBuilder {
+
+    pub fn new() -> DatabaseBuilder {
+        DatabaseBuilder { entries: Vec::new(), min_moves: 42, max_moves: 0 }
+    }
+
+    // adds a position and its score for the player to play. only regular connect 4 positions can be
+    // added, since other rules hash positions differently
+    pub fn add(&mut self, pos: &Position, score: i8) {
+        assert!(pos.rules == Rules::Standard, "only regular connect 4 positions can be stored");
+        self.entries.push(pos.hash() | (score as u8 as u64) << 56);
+        self.min_moves = self.min_moves.min(pos.num_moves());
+        self.max_moves = self.max_moves.max(pos.num_moves());
+    }
+
+    // adds every position of another database, for merging several into one
+    pub fn extend_from(&mut self, database: &Database) {
+        self.entries.extend((0..database.len).map(|i| database.entry(i)));
+        self.min_moves = self.min_moves.min(database.min_moves);
+        self.max_moves = self.max_moves.max(database.max_moves);
+    }
+
+    // the file contents, with every position once. when a position was added more than once, the
+    // score added first is kept
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries = self.entries.clone();
+        entries.sort_by_key(|entry| entry & KEY_MASK);
+        entries.dedup_by_key(|entry| *entry & KEY_MASK);
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + 8 * entries.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(entries.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.max_moves as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.min_moves as u64).to_le_bytes());
+        for entry in entries {
+            bytes.extend_from_slice(&entry.to_le_bytes());
+        }
+        bytes
+    }
+
+    // writes to a temporary file first and renames it, so a database that is already open is not
+    // changed underneath its map
+    pub fn write<P: AsRef<Path>>(&self, file_path: P) -> io::Result<()> {
+        let temp = file_path.as_ref().with_extension("tmp");
+        fs::write(&temp, self.to_bytes())?;
+        fs::rename(&temp, file_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::thread;
+    use crate::bench::read_test_file;
+    use crate::Solver;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("c4db-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn test_build_and_look_up() {
+        let cases = read_test_file("test_files/Middle-Easy.txt").unwrap();
+        let mut builder = DatabaseBuilder::new();
+        for case in &cases[..200] {
+            builder.add(&Position::from_moves(&case.moves).unwrap(), case.score);
+        }
+        let path = temp_path("lookup");
+        builder.write(&path).unwrap();
+        let database = Arc::new(Database::open(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+        assert!(database.len() <= 200 && database.len() > 190);
+
+        // looked up from several threads at once
+        let cases = &cases;
+        thread::scope(|s| {
+            for chunk in cases[..200].chunks(50) {
+                let database = Arc::clone(&database);
+                s.spawn(move || {
+                    for case in chunk {
+                        assert_eq!(database.get(&Position::from_moves(&case.moves).unwrap()), Some(case.score));
+                    }
+                });
+            }
+        });
+        assert_eq!(database.get(&Position::from_moves(&cases[500].moves).unwrap()), None);
+        assert_eq!(database.get(&Position::new()), None);
+    }
+
+    #[test]
+    fn test_negative_scores_and_mirrors() {
+        let mut builder = DatabaseBuilder::new();
+        builder.add(&Position::from_moves("4453").unwrap(), -3);
+        builder.add(&Position::from_moves("4435").unwrap(), 5); // the mirror, already added
+        let path = temp_path("mirror");
+        builder.write(&path).unwrap();
+        let database = Database::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(database.len(), 1);
+        assert_eq!(database.get(&Position::from_moves("4435").unwrap()), Some(-3));
+    }
+
+    #[test]
+    fn test_merge() {
+        let (first, second) = (temp_path("merge-1"), temp_path("merge-2"));
+        let mut builder = DatabaseBuilder::new();
+        builder.add(&Position::from_moves("4453").unwrap(), -3);
+        builder.write(&first).unwrap();
+        let mut builder = DatabaseBuilder::new();
+        builder.add(&Position::from_moves("44444433").unwrap(), 2);
+        builder.write(&second).unwrap();
+
+        let mut merged = DatabaseBuilder::new();
+        merged.extend_from(&Database::open(&first).unwrap());
+        merged.extend_from(&Database::open(&second).unwrap());
+        merged.write(&first).unwrap();
+        let database = Database::open(&first).unwrap();
+        fs::remove_file(&first).unwrap();
+        fs::remove_file(&second).unwrap();
+
+        assert_eq!((database.min_moves(), database.max_moves()), (4, 8));
+        assert_eq!(database.iter().count(), 2);
+        assert!(database.iter().map(|(hash, _)| hash).is_sorted());
+        assert_eq!(database.get(&Position::from_moves("4453").unwrap()), Some(-3));
+        assert_eq!(database.get(&Position::from_moves("44444433").unwrap()), Some(2));
+    }
+
+    #[test]
+    fn test_rejects_bad_files() {
+        let path = temp_path("bad");
+        let mut bytes = DatabaseBuilder::new().to_bytes();
+        bytes.push(0);
+        fs::write(&path, &bytes).unwrap();
+        assert!(Database::open(&path).is_err());
+        fs::write(&path, b"C4TT").unwrap();
+        assert!(Database::open(&path).is_err());
+
+        // a # of entries so big the size of the file would overflow
+        let mut bytes = DatabaseBuilder::new().to_bytes();
+        bytes[8..16].copy_from_slice(&(1_u64 << 61).to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(Database::open(&path).err().unwrap().kind(), io::ErrorKind::InvalidData);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_solver_uses_database() {
+        // a database that lies about a position is believed
+        let mut builder = DatabaseBuilder::new();
+        let mut pos = Position::from_moves("44444433").unwrap();
+        builder.add(&pos, -9);
+        let path = temp_path("solver");
+        builder.write(&path).unwrap();
+        let mut solver = Solver::new();
+        solver.set_database(Some(Arc::new(Database::open(&path).unwrap())));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(solver.score(&mut pos).unwrap(), (-9, 0));
+    }
+}
@@ -0,0 +1,200 @@
+// importing connect-4.data from the UCI machine learning repository, every legal position after 8
+// moves where nobody has won, labelled with the outcome for the first player (x) under perfect play.
+//
+// each line is 42 cells and a label, like "b,b,b,b,b,b,b,b,b,b,b,b,x,o,b,...,win". the cells go up
+// each column from the bottom, starting with the leftmost column (a1, a2, ..., a6, b1, ..., g6),
+// and are x, o or b for blank
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+use crate::{Position, Solver};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    // the outcome for the first player, given a score for the player to play
+    pub fn from_score(pos: &Position, score: i8) -> Outcome {
+        let score = if pos.turn == 0 { score } else { -score };
+        match score {
+            0 => Outcome::Draw,
+            s if s > 0 => Outcome::Win,
+            _ => Outcome::Loss,
+        }
+    }
+}
+
+pub struct Entry {
+    pub pos: Position,
+    pub moves: String, // a way of reaching the position, numbered 1-7 like in the test files
+    pub outcome: Outcome,
+}
+
+// reads a whole file, returns an error for the first line that is not a valid entry
+pub fn read_file<P: AsRef<Path>>(file_path: P) -> io::Result<Vec<Entry>> {
+    let contents = fs::read_to_string(&file_path)?;
+    contents.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()).map(|(i, line)| {
+        parse_line(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {e}", i + 1)))
+    }).collect()
+}
+
+pub fn parse_line(line: &str) -> Result<Entry, String> {
+    let fields: Vec<&str> = line.trim().split(',').collect();
+    if fields.len() != 43 { return Err(format!("expected 43 fields but found {}", fields.len())) }
+    let outcome = match fields[42] {
+        "win" => Outcome::Win,
+        "draw" => Outcome::Draw,
+        "loss" => Outcome::Loss,
+        label => return Err(format!("unknown label {label}")),
+    };
+    let (pos, moves) = from_cells(&fields[..42])?;
+    Ok(Entry { pos, moves, outcome })
+}
+
+// builds a position from 42 x/o/b cells, finding an order the stones could have been played in
+pub fn from_cells(cells: &[&str]) -> Result<(Position, String), String> {
+    let mut columns: Vec<Vec<usize>> = vec![Vec::new(); 7];
+    for (i, &cell) in cells.iter().enumerate() {
+        let col = &mut columns[i / 6];
+        match cell {
+            "x" | "o" if col.len() < i % 6 => return Err(format!("cell {} is floating", i + 1)),
+            "x" => col.push(0),
+            "o" => col.push(1),
+            "b" => (),
+            other => return Err(format!("unknown cell {other}")),
+        }
+    }
+
+    // x moves first, so x has as many stones as o, or one more when it is o's turn
+    let stones = |player| columns.iter().flatten().filter(|&&p| p == player).count();
+    let (x, o) = (stones(0), stones(1));
+    if x != o && x != o + 1 { return Err(format!("{x} x stones and {o} o stones cannot happen in a game")) }
+
+    // take stones off the top, last move first, then play them back in order
+    let mut order = Vec::new();
+    if !unplay(&mut columns, if x == o { 1 } else { 0 }, &mut order) {
+        return Err(String::from("no order of moves leads to this position"))
+    }
+    let moves: String = order.iter().rev().map(|&col| char::from_digit(col as u32 + 1, 10).unwrap()).collect();
+    let pos = Position::from_moves(&moves).map_err(|_| String::from("somebody has already won"))?;
+    Ok((pos, moves))
+}
+
+// removes the stones that were played last, alternating players, until the board is empty
+fn unplay(columns: &mut [Vec<usize>], player: usize, order: &mut Vec<usize>) -> bool {
+    if columns.iter().all(|col| col.is_empty()) { return true }
+    for col in 0..7 {
+        if columns[col].last() != Some(&player) { continue }
+        columns[col].pop();
+        order.push(col);
+        if unplay(columns, 1 - player, order) { return true }
+        order.pop();
+        columns[col].push(player);
+    }
+    false
+}
+
+// writes a position as a line of the file
+pub fn to_line(pos: &Position, outcome: Outcome) -> String {
+    let mut fields = Vec::new();
+    for col in 0..7 {
+        for row in 0..6 {
+            let spot = 1 << (7 * col + row);
+            fields.push(if pos.board[0] & spot != 0 { "x" } else if pos.board[1] & spot != 0 { "o" } else { "b" });
+        }
+    }
+    fields.push(match outcome {
+        Outcome::Win => "win",
+        Outcome::Draw => "draw",
+        Outcome::Loss => "loss",
+    });
+    fields.join(",")
+}
+
+// solves every entry, given with its line number, split across the given number of threads, and
+// returns the line number, label and solved outcome of every entry with the wrong label
+pub fn verify(entries: &[(usize, &Entry)], threads: usize) -> Vec<(usize, Outcome, Outcome)> {
+    let chunk_size = entries.len().div_ceil(threads.max(1)).max(1);
+    let mut wrong: Vec<(usize, Outcome, Outcome)> = thread::scope(|s| {
+        let workers: Vec<_> = entries.chunks(chunk_size).map(|entries| {
+            s.spawn(move || {
+                let mut solver = Solver::new();
+                let mut found = Vec::new();
+                for &(line, entry) in entries {
+                    let mut pos = entry.pos.clone();
+                    let (score, _) = solver.score(&mut pos).unwrap();
+                    let solved = Outcome::from_score(&pos, score);
+                    if solved != entry.outcome { found.push((line, entry.outcome, solved)) }
+                }
+                found
+            })
+        }).collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+    wrong.sort_by_key(|&(line, _, _)| line);
+    wrong
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::read_test_file;
+
+    // the first line of connect-4.data
+    const FIRST_LINE: &str = "b,b,b,b,b,b,b,b,b,b,b,b,x,o,b,b,b,b,x,o,x,o,x,o,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,win";
+
+    #[test]
+    fn test_parse_line() {
+        let entry = parse_line(FIRST_LINE).unwrap();
+        assert_eq!(entry.outcome, Outcome::Win);
+        assert_eq!(entry.pos.num_moves(), 8);
+        assert_eq!(entry.pos.key(), Position::from_moves("44444433").unwrap().key());
+        assert_eq!(to_line(&entry.pos, entry.outcome), FIRST_LINE);
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        assert!(parse_line("b,x,win").is_err());
+        assert!(parse_line(&FIRST_LINE.replace("win", "tie")).is_err());
+        assert!(parse_line(&FIRST_LINE.replacen("x", "o", 1)).err().unwrap().contains("cannot happen"));
+        assert_eq!(parse_line(&FIRST_LINE.replacen("b,b", "b,x", 1)).err().unwrap(), "cell 2 is floating");
+
+        // x has four in a row in the bottom row
+        let won = "x,b,b,b,b,b,x,o,b,b,b,b,x,o,b,b,b,b,x,o,b,b,b,b,o,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,win";
+        assert_eq!(parse_line(won).err().unwrap(), "somebody has already won");
+    }
+
+    #[test]
+    fn test_reconstructs_games() {
+        // stones that must come off in a particular order, or the players stop alternating
+        for moves in ["4455", "71122", "44444432", "2252576253462244111563365343671351441"] {
+            let pos = Position::from_moves(moves).unwrap();
+            let entry = parse_line(&to_line(&pos, Outcome::Draw)).unwrap();
+            assert_eq!(entry.pos.key(), pos.key());
+            assert_eq!(entry.pos.turn, pos.turn);
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        // positions from a test file in this format, with the outcome for x
+        let cases = read_test_file("test_files/End-Easy.txt").unwrap();
+        let entries: Vec<Entry> = cases.iter().take(30).map(|case| {
+            let pos = Position::from_moves(&case.moves).unwrap();
+            parse_line(&to_line(&pos, Outcome::from_score(&pos, case.score))).unwrap()
+        }).collect();
+        let mut numbered: Vec<(usize, &Entry)> = entries.iter().enumerate().map(|(i, e)| (i + 1, e)).collect();
+        assert!(verify(&numbered, 3).is_empty());
+
+        let mut flipped = parse_line(&to_line(&entries[4].pos, Outcome::Draw)).unwrap();
+        if entries[4].outcome == Outcome::Draw { flipped.outcome = Outcome::Win }
+        numbered[4].1 = &flipped;
+        assert_eq!(verify(&numbered, 3), [(5, flipped.outcome, entries[4].outcome)]);
+    }
+}
@@ -0,0 +1,134 @@
+// a bot with a difficulty level, so humans have a chance against it.
+// it still solves every column exactly, but at lower levels it sometimes picks a worse move on purpose.
+// the worse move is never a blunder though: it always takes a win on the spot, and it never plays
+// a move that lets the opponent win on the very next turn
+
+use rand::prelude::*;
+use crate::{analyze, Position};
+
+// from this level on, the bot never plays a move that changes the outcome (win, tie or loss)
+const KEEPS_OUTCOME_LEVEL: u8 = 6;
+
+pub struct Bot {
+    level: u8, // 1 (weakest) to 10 (perfect play)
+    rng: StdRng,
+}
+
+impl Bot {
+
+    // creates a bot for a level from 1 to 10, a fixed seed makes its choices reproducible
+    pub fn new(level: u8, seed: Option<u64>) -> Bot {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Bot { level: level.clamp(1, 10), rng }
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    // picks a move for the player to play (assumes the game is not over)
+    pub fn choose_move(&mut self, pos: &mut Position) -> usize {
+        let scores = analyze(pos);
+        let best = scores.iter().flatten().copied().max().unwrap();
+        let best_moves: Vec<usize> = (0..7).filter(|&col| scores[col] == Some(best)).collect();
+
+        // the chance of not playing the best move, 90% at level 1 down to never at level 10
+        let mistake_chance = (10 - self.level) as f64 / 10.0;
+
+        // a win this turn is always taken
+        let immediate_win = ((43 - pos.moves.len()) / 2) as i8;
+        if best == immediate_win || !self.rng.gen_bool(mistake_chance) {
+            return *best_moves.choose(&mut self.rng).unwrap();
+        }
+
+        // a move that lets the opponent win next turn is a blunder
+        let immediate_loss = -(((42 - pos.moves.len()) / 2) as i8);
+        let alternatives: Vec<usize> = (0..7).filter(|&col| match scores[col] {
+            Some(s) => s < best && s > immediate_loss && (self.level < KEEPS_OUTCOME_LEVEL || s.signum() == best.signum()),
+            None => false,
+        }).collect();
+
+        match alternatives.choose(&mut self.rng) {
+            Some(&mv) => mv,
+            None => *best_moves.choose(&mut self.rng).unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(key: &str) -> Position {
+        let mut p = Position::new();
+        for c in key.chars() {
+            p.make_move(c.to_digit(10).unwrap() as usize - 1);
+        }
+        p
+    }
+
+    // picks a move many times with a fresh bot each time
+    fn choices(level: u8, key: &str) -> Vec<usize> {
+        (0..20).map(|seed| Bot::new(level, Some(seed)).choose_move(&mut position(key))).collect()
+    }
+
+    #[test]
+    fn test_level_10_is_perfect() {
+        let key = "7422341735647741166133573473242566";
+        let scores = analyze(&mut position(key));
+        let best = scores.iter().flatten().max().copied();
+        for mv in choices(10, key) {
+            assert_eq!(scores[mv], best);
+        }
+    }
+
+    #[test]
+    fn test_never_blunders() {
+        // playing the last column lets the opponent win on top of it
+        let key = "2252576253462244111563365343671351441";
+        let mut p = position(key);
+        p.make_move(6);
+        assert!(p.is_winning_move(6));
+
+        assert!(choices(1, key).iter().all(|&mv| mv == 5));
+    }
+
+    #[test]
+    fn test_always_takes_win() {
+        assert!(choices(1, "334455").iter().all(|&mv| mv == 1 || mv == 5));
+    }
+
+    #[test]
+    fn test_strong_levels_keep_wins() {
+        let key = "7422341735647741166133573473242566";
+        let scores = analyze(&mut position(key));
+        assert!(scores.iter().flatten().any(|&s| s > 0));
+        for mv in choices(6, key) {
+            assert!(scores[mv].unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn test_weak_levels_make_mistakes() {
+        let key = "7422341735647741166133573473242566";
+        let scores = analyze(&mut position(key));
+        let best = scores.iter().flatten().max().copied();
+        assert!(choices(1, key).iter().any(|&mv| scores[mv] != best));
+    }
+
+    #[test]
+    fn test_seed_is_reproducible() {
+        let mut a = Bot::new(3, Some(28));
+        let mut b = Bot::new(3, Some(28));
+        let mut p = position("7422341735647741166133573473242566");
+        for _ in 0..4 {
+            let mv = a.choose_move(&mut p);
+            assert_eq!(b.choose_move(&mut p), mv);
+            if p.is_winning_move(mv) { break }
+            p.make_move(mv);
+        }
+    }
+}
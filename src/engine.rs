@@ -0,0 +1,303 @@
+// a line based text protocol for guis and tournament managers, modelled on the uci protocol from chess.
+// columns are numbered 1-7, like in the test files.
+//
+// commands:
+//   uci                              replies with the engine name and uciok
+//   isready                          replies readyok, straight away even while searching
+//   ucinewgame                       forgets everything learned in earlier searches
+//   position startpos [moves 4453]   sets the position to search
+//   go [movetime <ms>] [infinite]    searches the position, until it is solved if there is no time limit
+//   stop                             stops searching, the best move found so far is still sent
+//   savetable <file>                 writes what earlier searches learned to a file, see src/table.rs
+//   loadtable <file>                 replaces what earlier searches learned with a saved table
+//   quit
+//
+// replies while searching:
+//   info depth <d> nodes <n> eval <e>       quick depth limited search, e is a heuristic evaluation
+//   info depth <d> nodes <n> score <s>      same, but the quick search already proved the score
+//   info currmove <c> score <s> nodes <n>   exact score of one column
+//   info nodes <n> score <s>                exact score of the position
+//   info string <text>                      what zugzwang theory says about the position, see src/zugzwang.rs
+//                                           and src/victor.rs
+//   bestmove <c>
+
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use crate::{heuristic, victor, zugzwang, Position, Solver};
+use crate::heuristic::HeuristicTable;
+
+// depth of the quick search that makes sure there is a move to play, even when stopped right away
+const QUICK_DEPTH: usize = 8;
+
+struct Engine<W> {
+    out: Arc<Mutex<W>>,
+    pos: Position,
+    solver: Option<Solver>, // none while a search has it
+    heuristic: Option<HeuristicTable>, // the quick search's table, none while a search has it
+    stop: Arc<AtomicBool>,
+    search: Option<JoinHandle<(Solver, HeuristicTable)>>,
+}
+
+// reads commands until quit or the end of the input. at the end of the input, a running search is
+// allowed to finish, so piping in a position and a go command works
+pub fn run<R: BufRead, W: Write + Send + 'static>(input: R, output: W) {
+    let solver = Solver::new();
+    let mut engine = Engine {
+        out: Arc::new(Mutex::new(output)),
+        pos: Position::new(),
+        stop: solver.stop_flag(),
+        solver: Some(solver),
+        heuristic: Some(HeuristicTable::new()),
+        search: None,
+    };
+
+    for line in input.lines() {
+        let Ok(line) = line else { break };
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("uci") => {
+                say(&engine.out, &format!("id name {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")));
+                say(&engine.out, "uciok");
+            },
+            Some("isready") => say(&engine.out, "readyok"),
+            Some("ucinewgame") => {
+                engine.finish(true);
+                engine.solver.as_mut().unwrap().clear();
+                engine.heuristic.as_mut().unwrap().clear();
+                engine.pos = Position::new();
+            },
+            Some("position") => {
+                engine.finish(true);
+                match parse_position(words) {
+                    Ok(pos) => engine.pos = pos,
+                    Err(e) => say(&engine.out, &format!("info string invalid position: {e}")),
+                }
+            },
+            Some("go") => {
+                engine.finish(true);
+                engine.go(words);
+            },
+            Some("stop") => engine.finish(true),
+            Some(command @ ("savetable" | "loadtable")) => {
+                engine.finish(true);
+                let Some(path) = words.next() else {
+                    say(&engine.out, &format!("info string {command} needs a file"));
+                    continue
+                };
+                let solver = engine.solver.as_mut().unwrap();
+                let result = if command == "savetable" { solver.save_table(path) } else { solver.load_table(path) };
+                if let Err(e) = result { say(&engine.out, &format!("info string {command} failed: {e}")) }
+            },
+            Some("quit") => {
+                engine.finish(true);
+                return
+            },
+            Some(command) => say(&engine.out, &format!("info string unknown command: {command}")),
+            None => (),
+        }
+    }
+    engine.finish(false);
+}
+
+impl<W: Write + Send + 'static> Engine<W> {
+
+    // starts searching the current position in the background
+    fn go<'a>(&mut self, mut words: impl Iterator<Item = &'a str>) {
+        let mut deadline = None;
+        while let Some(word) = words.next() {
+            if word == "movetime" {
+                match words.next().and_then(|ms| ms.parse().ok()) {
+                    Some(ms) => deadline = Some(Instant::now() + Duration::from_millis(ms)),
+                    None => say(&self.out, "info string movetime needs a number of milliseconds"),
+                }
+            }
+        }
+
+        let mut solver = self.solver.take().unwrap();
+        let heuristic = self.heuristic.take().unwrap();
+        solver.set_deadline(deadline);
+        self.stop.store(false, Ordering::Relaxed);
+
+        let pos = self.pos.clone();
+        let out = Arc::clone(&self.out);
+        self.search = Some(thread::spawn(move || think(solver, heuristic, pos, &out)));
+    }
+
+    // waits for the running search, if there is one, and takes back its solver and heuristic table
+    fn finish(&mut self, stop: bool) {
+        if let Some(search) = self.search.take() {
+            if stop { self.stop.store(true, Ordering::Relaxed) }
+            let (solver, heuristic) = search.join().unwrap();
+            self.solver = Some(solver);
+            self.heuristic = Some(heuristic);
+        }
+    }
+}
+
+// parses the rest of a position command
+fn parse_position<'a>(mut words: impl Iterator<Item = &'a str>) -> Result<Position, String> {
+    if words.next() != Some("startpos") { return Err(String::from("expected startpos")) }
+    match (words.next(), words.next()) {
+        (None, _) => Ok(Position::new()),
+        (Some("moves"), moves) => Position::from_moves(moves.unwrap_or("")),
+        _ => Err(String::from("expected moves")),
+    }
+}
+
+fn say<W: Write>(out: &Mutex<W>, line: &str) {
+    let mut out = out.lock().unwrap();
+    let _ = writeln!(out, "{line}");
+    let _ = out.flush();
+}
+
+// searches a position and sends the best move, returns the solver and heuristic table so they can
+// be reused
+fn think<W: Write>(mut solver: Solver, mut heuristic: HeuristicTable, mut pos: Position, out: &Mutex<W>) -> (Solver, HeuristicTable) {
+    if pos.moves.len() == 42 {
+        say(out, "bestmove none");
+        return (solver, heuristic)
+    }
+
+    for line in zugzwang::explain(&pos).into_iter().chain(victor::explain(&pos)) {
+        say(out, &format!("info string {line}"));
+    }
+
+    // a quick depth limited search first, so there is a move to play however soon we are stopped
+    let depth = QUICK_DEPTH.min(42 - pos.moves.len());
+    let (quick_move, eval, mut nodes) = heuristic::search(&mut pos, depth, &mut heuristic);
+    match heuristic::to_exact(eval) {
+        Some(s) => say(out, &format!("info depth {depth} nodes {nodes} score {s}")),
+        None => say(out, &format!("info depth {depth} nodes {nodes} eval {eval}")),
+    }
+
+    // then solve the columns exactly, most promising first
+    let mut scores: [Option<i8>; 7] = [None; 7];
+    let mut complete = true;
+    for col in [quick_move, 3, 2, 4, 1, 5, 0, 6] {
+        if !pos.is_legal_move(col) || scores[col].is_some() { continue }
+        let s = if pos.is_winning_move(col) {
+            ((43 - pos.moves.len()) / 2) as i8
+        } else {
+            pos.make_move(col);
+            let result = solver.score(&mut pos);
+            pos.undo_move();
+            match result {
+                Some((s, p)) => {
+                    nodes += p;
+                    -s
+                },
+                None => {
+                    complete = false;
+                    break
+                },
+            }
+        };
+        scores[col] = Some(s);
+        say(out, &format!("info currmove {} score {s} nodes {nodes}", col + 1));
+    }
+
+    // the best solved column, unless we were stopped before knowing it is better than the quick move
+    let best = (0..7).filter(|&col| scores[col].is_some()).max_by_key(|&col| (scores[col], col == quick_move));
+    let mv = match best {
+        Some(col) if complete => {
+            say(out, &format!("info nodes {nodes} score {}", scores[col].unwrap()));
+            col
+        },
+        Some(col) if scores[quick_move].is_some() || scores[col].unwrap() > 0 => col,
+        _ => quick_move,
+    };
+    say(out, &format!("bestmove {}", mv + 1));
+    (solver, heuristic)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // output that the test can still read after the engine is done with it
+    #[derive(Clone)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run_commands(commands: &str) -> Vec<String> {
+        let out = Shared(Arc::new(Mutex::new(Vec::new())));
+        run(Cursor::new(commands.to_string()), out.clone());
+        let text = String::from_utf8(out.0.lock().unwrap().clone()).unwrap();
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_handshake() {
+        let lines = run_commands("uci\nisready\nquit\n");
+        assert!(lines[0].starts_with("id name"));
+        assert_eq!(lines[1..], ["uciok", "readyok"]);
+    }
+
+    #[test]
+    fn test_solves_position() {
+        let lines = run_commands("position startpos moves 2252576253462244111563365343671351441\ngo\n");
+        assert!(lines.iter().any(|l| l.starts_with("info currmove 7 score -2")));
+        assert!(lines.iter().any(|l| l.starts_with("info nodes") && l.ends_with("score -1")));
+        assert_eq!(lines.last().unwrap(), "bestmove 6");
+    }
+
+    #[test]
+    fn test_explains_zugzwang() {
+        let lines = run_commands("position startpos moves 66513447136647211277114\ngo\n");
+        assert!(lines.contains(&String::from("info string first player has 2 odd threats and 0 even threats")));
+        assert!(lines.iter().any(|l| l.starts_with("info string odd threat")));
+    }
+
+    #[test]
+    fn test_explains_victor() {
+        let lines = run_commands("position startpos moves 31351514213474435441723165222257767735\ngo\n");
+        assert!(lines.iter().any(|l| l.starts_with("info string victor: the second player")));
+        assert!(lines.iter().any(|l| l.starts_with("info string claimeven")));
+    }
+
+    #[test]
+    fn test_stop() {
+        let start = Instant::now();
+        let lines = run_commands("position startpos\ngo\nisready\nstop\nquit\n");
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert!(lines.contains(&String::from("readyok")));
+        assert!(lines.last().unwrap().starts_with("bestmove"));
+    }
+
+    #[test]
+    fn test_movetime() {
+        let lines = run_commands("position startpos moves 44\ngo movetime 50\n");
+        assert!(lines.last().unwrap().starts_with("bestmove"));
+        assert!(!lines.iter().any(|l| l.starts_with("info nodes")));
+    }
+
+    #[test]
+    fn test_save_and_load_table() {
+        let path = std::env::temp_dir().join(format!("c4tt-engine-test-{}", std::process::id()));
+        let path = path.display();
+        let lines = run_commands(&format!("savetable {path}\nloadtable {path}\nloadtable {path}.missing\nsavetable\n"));
+        std::fs::remove_file(path.to_string()).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("info string loadtable failed"));
+        assert_eq!(lines[1], "info string savetable needs a file");
+    }
+
+    #[test]
+    fn test_invalid_position() {
+        let lines = run_commands("position startpos moves 4444444\nposition sideways\n");
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.starts_with("info string invalid position")));
+    }
+}
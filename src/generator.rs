@@ -0,0 +1,178 @@
+// generating new benchmark positions, in the same "moves score" format as the test files.
+// positions are reached by random play, solved, and labelled by how many positions the solver
+// had to search for them
+
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+use rand::prelude::*;
+use crate::{Position, Solver};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Bucket {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+// a solved position, moves are numbered 1-7 like in the test files
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generated {
+    pub moves: String,
+    pub score: i8,
+    pub nodes: u64,
+    pub bucket: Bucket,
+}
+
+// what came of trying to generate a position
+#[derive(Debug, Clone, PartialEq)]
+pub enum Attempt {
+    Solved(Generated),
+    TimedOut, // the position took longer than the timeout to solve
+    UsedUp, // no new position could be found in the ply range
+}
+
+impl fmt::Display for Generated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.moves, self.score)
+    }
+}
+
+pub struct Generator {
+    pub plies: RangeInclusive<usize>, // how many moves the positions have been played into
+    pub easy_limit: u64, // positions searched, below this is easy
+    pub medium_limit: u64, // below this is medium, the rest are hard
+    pub timeout: Option<Duration>, // positions that take longer to solve are thrown away
+    seen: HashSet<u64>, // hashes of the positions so far, so mirror images count as the same position
+    rng: StdRng,
+}
+
+// random positions in a row that can be already seen before the ply range counts as used up
+const MAX_MISSES: usize = 10_000;
+
+impl Generator {
+
+    // the default limits put the Easy and Medium test files in the matching buckets, a fixed seed
+    // makes the positions reproducible
+    pub fn new(plies: RangeInclusive<usize>, seed: Option<u64>) -> Generator {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Generator { plies, easy_limit: 10_000, medium_limit: 1_000_000, timeout: None, seen: HashSet::new(), rng }
+    }
+
+    pub fn bucket(&self, nodes: u64) -> Bucket {
+        if nodes < self.easy_limit {
+            Bucket::Easy
+        } else if nodes < self.medium_limit {
+            Bucket::Medium
+        } else {
+            Bucket::Hard
+        }
+    }
+
+    // a random legal position in the ply range that nobody has won, where the player to play cannot
+    // win on the spot. the same position (or its mirror image) is never returned twice, so this is
+    // none once the random positions keep being ones that were already returned
+    pub fn random_position(&mut self) -> Option<(Position, String)> {
+        let min = *self.plies.start();
+        let max = (*self.plies.end()).min(41);
+        assert!(min <= max, "positions need between 0 and 41 moves");
+
+        for _ in 0..MAX_MISSES {
+            let plies = self.rng.gen_range(min..=max);
+            let mut pos = Position::new();
+            let mut moves = String::new();
+            while pos.moves.len() < plies {
+                let options: Vec<usize> = (0..7).filter(|&col| pos.is_legal_move(col) && !pos.is_winning_move(col)).collect();
+                let Some(&col) = options.choose(&mut self.rng) else { break };
+                pos.make_move(col);
+                moves.push(char::from_digit(col as u32 + 1, 10).unwrap());
+            }
+            if pos.moves.len() < plies || (0..7).any(|col| pos.is_legal_move(col) && pos.is_winning_move(col)) { continue }
+            if self.seen.insert(pos.hash()) { return Some((pos, moves)) }
+        }
+        None
+    }
+
+    // solves a new random position
+    pub fn generate(&mut self) -> Attempt {
+        let Some((mut pos, moves)) = self.random_position() else { return Attempt::UsedUp };
+        let mut solver = Solver::new();
+        solver.set_deadline(self.timeout.map(|timeout| Instant::now() + timeout));
+        match solver.score(&mut pos) {
+            Some((score, nodes)) => Attempt::Solved(Generated { moves, score, nodes, bucket: self.bucket(nodes) }),
+            None => Attempt::TimedOut,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::score;
+
+    #[test]
+    fn test_random_positions() {
+        let mut generator = Generator::new(20..=30, Some(34));
+        for _ in 0..50 {
+            let (mut pos, moves) = generator.random_position().unwrap();
+            assert!((20..=30).contains(&pos.moves.len()));
+            assert!((0..7).all(|col| !pos.is_legal_move(col) || !pos.is_winning_move(col)));
+            assert_eq!(Position::from_moves(&moves).unwrap().board, pos.board);
+            assert!(score(&mut pos).0.abs() <= 21);
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        let mut generator = Generator::new(30..=34, Some(7));
+        let Attempt::Solved(generated) = generator.generate() else { panic!("not solved") };
+        let mut pos = Position::from_moves(&generated.moves).unwrap();
+        assert_eq!(generated.score, score(&mut pos).0);
+        assert_eq!(generated.to_string(), format!("{} {}", generated.moves, generated.score));
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let moves = |seed| Generator::new(10..=12, Some(seed)).random_position().unwrap().1;
+        assert_eq!(moves(1), moves(1));
+        assert_ne!(moves(1), moves(2));
+    }
+
+    #[test]
+    fn test_buckets() {
+        let generator = Generator::new(0..=0, None);
+        assert_eq!(generator.bucket(56), Bucket::Easy);
+        assert_eq!(generator.bucket(36_090), Bucket::Medium);
+        assert_eq!(generator.bucket(5_000_000), Bucket::Hard);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut generator = Generator::new(0..=2, Some(3));
+        generator.timeout = Some(Duration::from_millis(1));
+        assert_eq!(generator.generate(), Attempt::TimedOut);
+    }
+
+    #[test]
+    fn test_used_up() {
+        // the empty board, then the 7 positions after one move, which are only 4 counting mirror images
+        let mut generator = Generator::new(0..=0, Some(5));
+        assert!(generator.random_position().is_some());
+        assert_eq!(generator.generate(), Attempt::UsedUp);
+
+        let mut generator = Generator::new(1..=1, Some(5));
+        let hashes: HashSet<u64> = (0..4).map(|_| generator.random_position().unwrap().0.hash()).collect();
+        assert_eq!(hashes.len(), 4);
+        assert!(generator.random_position().is_none());
+    }
+}
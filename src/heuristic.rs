@@ -0,0 +1,299 @@
+// depth limited search, for when solving the position exactly would take too long (blitz games,
+// or variants like the cylinder). past the depth limit, positions are scored by an evaluation
+// function instead of being searched to the end of the game.
+
+// heuristic scores fit in the same packed transposition table entries as exact scores:
+//  -10 to 10 are evaluations, where positive means the player to play is better
+//  anything bigger is a proven result: an exact score s becomes s + 10 (or s - 10 when negative)
+
+// the entries are packed like the ones from negamax, with the search depth as their effort, but go in
+// a table of their own. a heuristic score in the solver's table would be taken for an exact one
+
+use crate::{bucket_entry, bucket_store, create_tt, decode, orient, ordering, tt_entry, zugzwang, Position};
+use crate::ordering::MoveOrderer;
+
+// how much a stone in each column is worth for center control
+const CENTER_WEIGHTS: [i32; 7] = [0, 1, 2, 3, 2, 1, 0];
+
+// what heuristic searches learned, kept between them
+pub struct HeuristicTable {
+    tt: Box<[u64; 1000003]>, // same layout and buckets as the solver's, see create_tt
+    orderer: Box<dyn MoveOrderer>, // decides which moves are searched first
+}
+
+impl Default for HeuristicTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeuristicTable {
+
+    pub fn new() -> HeuristicTable {
+        HeuristicTable { tt: create_tt(), orderer: Box::new(ordering::ThreatFirst) }
+    }
+
+    // replaces how moves are ordered, see src/ordering.rs
+    pub fn set_move_orderer(&mut self, orderer: Box<dyn MoveOrderer>) {
+        self.orderer = orderer;
+    }
+
+    // forget all previous searches
+    pub fn clear(&mut self) {
+        self.tt.fill(0);
+        self.orderer.clear();
+    }
+
+    fn entry(&self, hash: u64) -> Option<u64> {
+        bucket_entry(&self.tt[..], hash)
+    }
+
+    fn store(&mut self, hash: u64, into_tt: u64) {
+        bucket_store(&mut self.tt[..], hash, into_tt)
+    }
+}
+
+// turns an exact score into a proven heuristic score
+fn proven(score: i8) -> i8 {
+    match score {
+        0 => 0,
+        s if s > 0 => s + 10,
+        s => s - 10,
+    }
+}
+
+// turns a heuristic score back into an exact score, none if it is only an evaluation
+pub fn to_exact(score: i8) -> Option<i8> {
+    if score > 10 { return Some(score - 10) }
+    if score < -10 { return Some(score + 10) }
+    None
+}
+
+// scores a position without searching, from the view of the player to play
+pub fn evaluate(pos: &Position) -> i8 {
+    let mut eval: i32 = 0;
+
+    for player in 0..2 {
+        let sign = if player == pos.turn { 1 } else { -1 };
+
+        // the first player wins with threats on odd rows, the second player with threats on even rows
+        let (odd, even) = zugzwang::by_parity(pos.threats(pos.board, player));
+        let (good, bad) = if player == 0 { (odd, even) } else { (even, odd) };
+        eval += sign * (2 * good.count_ones() + bad.count_ones()) as i32;
+
+        // a cylinder has no center to control
+        if pos.rules == crate::Rules::Standard {
+            let mut center = 0;
+            for (col, weight) in CENTER_WEIGHTS.iter().enumerate() {
+                center += weight * (pos.board[player] >> (7 * col) & 0b111111).count_ones() as i32;
+            }
+            eval += sign * center / 4;
+        }
+    }
+
+    eval.clamp(-10, 10) as i8
+}
+
+// searches a position to a given depth, returns the best move, its score and # positions searched
+pub fn search(pos: &mut Position, depth: usize, table: &mut HeuristicTable) -> (usize, i8, u64) {
+    let mut total_positions: u64 = 1;
+
+    // like negamax, alphabeta expects the previous move to have blocked any win this turn
+    for col in [3, 2, 4, 1, 5, 0, 6] {
+        if pos.is_legal_move(col) && pos.is_winning_move(col) {
+            return (col, proven(((43 - pos.moves.len()) / 2) as i8), total_positions)
+        }
+    }
+
+    // alphabeta also expects us to have blocked the opponent, so moves that do not are a loss
+    let threats = pos.threats(pos.board, 1 - pos.turn);
+    let live_threats = pos.get_live_threats(threats);
+    let must_play_move = pos.must_play_move(live_threats);
+    let loss = proven((-42 + pos.moves.len() as i8) / 2);
+    let doomed = pos.is_losing_position(threats, live_threats);
+
+    let mut best_move = 7;
+    let mut alpha: i8 = loss;
+    for mv in table.orderer.order([3, 2, 4, 1, 5, 0, 6], pos) {
+        if !pos.is_legal_move(mv) { continue }
+        if best_move == 7 { best_move = mv }
+        if doomed || pos.is_losing_move(mv, threats) || (must_play_move < 7 && mv != must_play_move) { continue }
+
+        pos.make_move(mv);
+        let (s, p) = alphabeta(pos, depth.saturating_sub(1), -31, -alpha, table);
+        pos.undo_move();
+        total_positions += p;
+
+        if -s > alpha {
+            alpha = -s;
+            best_move = mv;
+        }
+    }
+    (best_move, alpha, total_positions)
+}
+
+// negamax with a depth limit, returns the score and how many positions were searched. entries are
+// stored with the depth as their effort, so they are only used by searches at most that deep
+fn alphabeta(pos: &mut Position, depth: usize, mut alpha: i8, mut beta: i8, table: &mut HeuristicTable) -> (i8, u64) {
+
+    // use prior search if one exists and it looked at least as deep
+    let hash = pos.hash();
+    let mut tt_move = None;
+    if let Some(tt_record) = table.entry(hash).filter(|&record| (record >> 57 & 0b1111) as usize >= depth) {
+        let (flag, score, mv) = decode(tt_record);
+        tt_move = mv.map(|mv| orient(pos, hash, mv));
+        if flag == 0b00 { // lowerbound
+            if score > alpha { alpha = score }
+        } else if flag == 0b01 { // exact
+            return (score, 0);
+        } else { // upperbound
+            if score < beta { beta = score }
+        }
+        if alpha >= beta { return (alpha, 0) };
+    }
+
+    let original_alpha = alpha;
+    let mut total_positions: u64 = 1;
+    let loss = proven((-42 + pos.moves.len() as i8) / 2);
+
+    // check if game is a tie
+    if pos.moves.len() == 42 { return (0, total_positions) };
+
+    let threats = pos.threats(pos.board, 1 - pos.turn);
+    let live_threats = pos.get_live_threats(threats);
+
+    // a loss on the opponents next turn is known without going any deeper
+    if pos.is_losing_position(threats, live_threats) { return (loss, total_positions) }
+
+    if depth == 0 { return (evaluate(pos), total_positions) }
+
+    let mut best_move = None;
+    let must_play_move = pos.must_play_move(live_threats);
+    if must_play_move < 7 {
+        pos.make_move(must_play_move);
+        let (s, p) = alphabeta(pos, depth - 1, -beta, -alpha, table);
+        pos.undo_move();
+        total_positions += p;
+        alpha = -s;
+        best_move = Some(must_play_move);
+    } else {
+        // the best move from an earlier search is searched first, then the rest in the table's order.
+        // if every move plays under an opponent threat, we lose right after it
+        let mut searched = false;
+        let ordered = table.orderer.order([3, 2, 4, 1, 5, 0, 6], pos);
+        for mv in tt_move.into_iter().chain(ordered.into_iter().filter(|&mv| Some(mv) != tt_move)) {
+            if pos.is_legal_move(mv) && !pos.is_losing_move(mv, threats) {
+                searched = true;
+                pos.make_move(mv);
+                let (s, p) = alphabeta(pos, depth - 1, -beta, -alpha, table);
+                pos.undo_move();
+                total_positions += p;
+                if -s > alpha {
+                    alpha = -s;
+                    best_move = Some(mv);
+                }
+                if alpha >= beta {
+                    table.orderer.cutoff(pos, mv);
+                    break
+                }
+            }
+        }
+        if !searched { return (loss, total_positions) }
+    }
+
+    // store score in the table, along with how deep it was searched
+    table.store(hash, tt_entry(pos, hash, original_alpha, alpha, beta, depth as u64, best_move));
+
+    (alpha, total_positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{score, Rules};
+
+    fn position(key: &str) -> Position {
+        let mut p = Position::new();
+        for c in key.chars() {
+            p.make_move(c.to_digit(10).unwrap() as usize - 1);
+        }
+        p
+    }
+
+    #[test]
+    fn test_evaluate_start() {
+        assert_eq!(evaluate(&Position::new()), 0);
+    }
+
+    #[test]
+    fn test_evaluate_center() {
+        // the player who took the center is better, whoever is to play
+        let mut p = position("4444");
+        p.make_move(3);
+        assert!(evaluate(&p) < 0);
+        p.make_move(0);
+        assert!(evaluate(&p) > 0);
+    }
+
+    #[test]
+    fn test_evaluate_threat_parity() {
+        // the same three stones for the first player, with a threat on row 1 (odd) or on row 2 (even)
+        let mut odd = Position::new();
+        odd.board = [1 | 1 << 7 | 1 << 14, 1 << 42];
+        let mut even = Position::new();
+        even.board = [odd.board[0] << 1, 1 << 42];
+        assert_eq!(evaluate(&odd), evaluate(&even) + 1);
+    }
+
+    #[test]
+    fn test_search_takes_win() {
+        let mut p = position("445566");
+        let mut table = HeuristicTable::new();
+        let (mv, s, _) = search(&mut p, 4, &mut table);
+        assert!(mv == 2 || mv == 6);
+        assert_eq!(to_exact(s), Some(18));
+    }
+
+    #[test]
+    fn test_search_finds_double_threat() {
+        let mut p = position("3355");
+        let mut table = HeuristicTable::new();
+        let (mv, s, _) = search(&mut p, 2, &mut table);
+        assert_eq!(mv, 3);
+        assert!(to_exact(s).unwrap() > 0);
+    }
+
+    #[test]
+    fn test_stores_depth_as_effort() {
+        let mut p = position("3355");
+        let mut table = HeuristicTable::new();
+        search(&mut p, 4, &mut table);
+
+        // the position after a move was searched 3 deep, and searching it deeper replaces the entry
+        p.make_move(0);
+        assert_eq!(table.entry(p.hash()).unwrap() >> 57 & 0b1111, 3);
+        alphabeta(&mut p, 5, -31, 31, &mut table);
+        assert_eq!(table.entry(p.hash()).unwrap() >> 57 & 0b1111, 5);
+    }
+
+    #[test]
+    fn test_full_depth_is_exact() {
+        for key in ["2252576253462244111563365343671351441", "7422341735647741166133573473242566"] {
+            let mut p = position(key);
+            let mut table = HeuristicTable::new();
+            let depth = 42 - p.moves.len();
+            let (_, s, _) = search(&mut p, depth, &mut table);
+            assert_eq!(to_exact(s).unwrap_or(0), score(&mut p).0);
+        }
+    }
+
+    #[test]
+    fn test_search_cylinder() {
+        let mut p = Position::with_rules(Rules::Cylinder);
+        p.make_moves(vec![5, 5, 6, 6]);
+        let mut table = HeuristicTable::new();
+        let (mv, s, _) = search(&mut p, 2, &mut table);
+        assert!([0, 4].contains(&mv));
+        assert!(to_exact(s).unwrap() > 0);
+    }
+}
@@ -1,18 +1,74 @@
+use std::io;
 use std::path::Path;
-use std::fs;
-use std::time::{Duration, SystemTime};
-use rand::prelude::*;
-use std::cmp::Ordering;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::time::Instant;
+use ordering::MoveOrderer;
+
+mod cylinder;
+pub mod bench;
+pub mod certificate;
+pub mod cli;
+pub mod database;
+pub mod dataset;
+pub mod difficulty;
+pub mod engine;
+pub mod generator;
+pub mod heuristic;
+pub mod mcts;
+pub mod ordering;
+pub mod perft;
+pub mod pons;
+pub mod record;
+pub mod strong;
+pub mod table;
+pub mod tablebase;
+pub mod verify;
+pub mod victor;
+pub mod zugzwang;
+#[cfg(feature = "server")]
+pub mod server;
+
+// the set of rules a position is played under
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rules {
+    Standard, // regular connect 4
+    Cylinder, // leftmost and rightmost columns are adjacent, so lines wrap around
+}
 
-struct Position {
+#[derive(Clone)]
+pub struct Position {
     board: [u64; 2], // stores bitboards for each player
     turn: usize, // tracks which player it is to play
     moves: Vec<usize>, // history of moves
     height_mask: u64, // tracks next playable spot for each column
+    rules: Rules, // which variant is being played
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Position {
 
+    // returns the start position of regular connect 4
+    pub fn new() -> Position {
+        Position::with_rules(Rules::Standard)
+    }
+
+    // returns the start position for a set of rules
+    pub fn with_rules(rules: Rules) -> Position {
+        Position {
+            board: [0, 0],
+            turn: 0,
+            moves: Vec::new(),
+            height_mask: 0b0000001000000100000010000001000000100000010000001,
+            rules,
+        }
+    }
+
     // returns height mask for a specific column
     pub fn get_col_height_mask(&self, col: usize) -> u64 {
         let col_mask: u64 = 0b1111111;
@@ -41,6 +97,33 @@ impl Position {
         }
     }
 
+    // play moves written as columns 1-7, like the test files use.
+    // a move that is not legal or that would win the game is an error, and nothing after it is played
+    pub fn play(&mut self, moves: &str) -> Result<(), String> {
+        for (i, c) in moves.chars().enumerate() {
+            let col = match c.to_digit(10) {
+                Some(col) if (1..=7).contains(&col) => col as usize - 1,
+                _ => return Err(format!("move {} is not a column: '{c}'", i + 1)),
+            };
+            if !self.is_legal_move(col) { return Err(format!("move {} is in a full column", i + 1)) }
+            if self.is_winning_move(col) { return Err(format!("move {} ends the game", i + 1)) }
+            self.make_move(col);
+        }
+        Ok(())
+    }
+
+    // regular connect 4 position after some moves, see play
+    pub fn from_moves(moves: &str) -> Result<Position, String> {
+        let mut pos = Position::new();
+        pos.play(moves)?;
+        Ok(pos)
+    }
+
+    // # of moves played so far
+    pub fn num_moves(&self) -> usize {
+        self.moves.len()
+    }
+
     // undo a move
     pub fn undo_move(&mut self) {
         let last_col = self.moves.pop().unwrap();
@@ -53,6 +136,7 @@ impl Position {
     // check if a move results in connect 4
     pub fn is_winning_move(&self, col: usize) -> bool {
         let b = self.board[self.turn] | self.get_col_height_mask(col);
+        if self.rules == Rules::Cylinder { return cylinder::is_connect_four(b) }
         if ((b & b << 1 & b << 2 & b << 3) |
             (b & b << 7 & b << 14 & b << 21) |
             (b & b << 6 & b << 12 & b << 18) |
@@ -87,32 +171,49 @@ impl Position {
         threats & self.height_mask
     }
 
+    // gets threats of a player under the rules of this position
+    pub fn threats(&self, board: [u64; 2], player: usize) -> u64 {
+        match self.rules {
+            Rules::Standard => get_threats(board, player),
+            Rules::Cylinder => cylinder::get_threats(board, player),
+        }
+    }
+
+    // get unique key that represents the position, without treating mirrored positions as the same
+    pub fn key(&self) -> u64 {
+        self.board[self.turn] | self.height_mask
+    }
+
     // get unique key that represents the position
     pub fn hash(&self) -> u64 {
-        let hash: u64 = self.board[self.turn] | self.height_mask;
+        let hash: u64 = self.key();
+
+        // a cylinder has no edges, so every rotation of the board is the same position too
+        if self.rules == Rules::Cylinder { return cylinder::hash(hash) }
 
+        // connect 4 is symmetrical, so the mirrored board has the same score.
         // arbitrarily return the lower hash, since both positions are essentially the same
-        if self.get_col_height_mask(0) >= self.get_col_height_mask(6) >> 42 {
-            return hash
-        } 
-
-        // connect 4 is symmetrical, so the mirrored board has the same score
-        let mut mirrored_hash: u64 = 0;
-        let col_mask: u64 = 127;
-        mirrored_hash |= (hash & col_mask) << 42;
-        mirrored_hash |= (hash & col_mask << 7) << 28;
-        mirrored_hash |= (hash & col_mask << 14) << 14;
-        mirrored_hash |= hash & col_mask << 21;
-        mirrored_hash |= (hash & col_mask << 28) >> 14;
-        mirrored_hash |= (hash & col_mask << 35) >> 28;
-        mirrored_hash |= (hash & col_mask << 42) >> 42;
-
-        mirrored_hash
+        hash.min(mirror(hash))
     }
 }
 
+// mirrors a position key, swapping column 0 with 6, 1 with 5 and 2 with 4
+fn mirror(hash: u64) -> u64 {
+    let mut mirrored_hash: u64 = 0;
+    let col_mask: u64 = 127;
+    mirrored_hash |= (hash & col_mask) << 42;
+    mirrored_hash |= (hash & col_mask << 7) << 28;
+    mirrored_hash |= (hash & col_mask << 14) << 14;
+    mirrored_hash |= hash & col_mask << 21;
+    mirrored_hash |= (hash & col_mask << 28) >> 14;
+    mirrored_hash |= (hash & col_mask << 35) >> 28;
+    mirrored_hash |= (hash & col_mask << 42) >> 42;
+
+    mirrored_hash
+}
+
 // gets threats of opponent, specifically where they are
-fn get_threats(board: [u64; 2], player: usize) -> u64 {
+pub fn get_threats(board: [u64; 2], player: usize) -> u64 {
     let open: u64 = !(board[0] | board[1] | 283691315109952 | 71776119061217280);
     let opp: u64 = board[player];
 
@@ -143,125 +244,415 @@ fn get_threats(board: [u64; 2], player: usize) -> u64 {
 }
 
 // creates empty transposition table
-fn create_tt() -> Box<[u64; 1000003]> {
+pub fn create_tt() -> Box<[u64; 1000003]> {
     // the tt stores u64s, so 64 bits of information
     // Bits 0-48 hold the key, to confirm we are colliding while searching
     // Bit 49-50 hold the alphabeta flag. 00 for lowerbound, 01 for exact, 10 for upperbound
     // Bit 51 holds the sign of the score. 1 is negative
     // Bits 52-56 holds the absolute value of the score
-    // Bits 57-63 are unused
-    Box::new([0; 1000003])
+    // Bits 57-60 hold the search effort, half the bit length of the # positions searched (at most 15)
+    // Bits 61-63 hold the best move + 1, 0 if there is none. it is the move for the position the hash
+    // was made from, so it is mirrored for the mirrored position
+
+    // entries are in buckets of two, starting at an even index. the first slot keeps whichever
+    // position took the most effort to search, the second always takes the newest position
+
+    // built on the heap, since Box::new would first place the 8mb array on the stack
+    vec![0; 1000003].into_boxed_slice().try_into().unwrap()
 }
 
 // takes a position, iteratively deepens to find its score, returns score and # positions searched
-fn score(pos: &mut Position) -> (i8, u64) {
-    // null window search window = [alpha, alpha + 1]
-    // two return options, score > alpha or score <= alpha
-    // by using a small window, we quickly determine if the true score is better or worse than alpha
-    // we then adjust our window towards to direction of the true score
+pub fn score(pos: &mut Position) -> (i8, u64) {
+    Solver::new().score(pos).unwrap()
+}
 
-    // counterintuitively these many searches are faster than a single search due to the small window resulting
-    // in a lot of pruning, and the transposition table helps us not repeat searches
+// scores every column for the player to play, none for columns that can not be played
+pub fn analyze(pos: &mut Position) -> [Option<i8>; 7] {
+    Solver::new().analyze(pos).unwrap()
+}
+
+// solves positions, keeping its transposition table between searches.
+// a search can be cut short with a deadline or by setting the stop flag from another thread
+pub struct Solver {
+    tt: Box<[u64; 1000003]>,
+    stop: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    polls: u64, // # negamax calls, to only check the clock every so often
+    stopped: bool, // set once a search notices it should stop
+    book: Option<Arc<pons::Book>>, // exact scores of early positions, looked up instead of searched
+    database: Option<Arc<database::Database>>, // same, but memory mapped and keyed by Position::hash
+    tablebase: Option<Arc<database::Database>>, // exact scores of late positions, see src/tablebase.rs
+    orderer: Box<dyn MoveOrderer>, // decides which moves negamax searches first
+    victor: Option<victor::Prover>, // proves second player draws and wins without searching, see src/victor.rs
+}
 
-    let mut min: i8 = -1 * ((42 - pos.moves.len()) / 2) as i8;
-    let mut max: i8 = ((43 - pos.moves.len()) / 2) as i8;
-    let mut tt = create_tt();
-    let mut positions_searched: u64 = 0;
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    while min < max {
-        let mut med: i8 = min + (max - min) / 2;
-        if med <= 0 && min / 2 < med {med = min / 2}
-        else if med >= 0 && max / 2 > med {med = max / 2}
+impl Solver {
+
+    pub fn new() -> Solver {
+        Solver {
+            tt: create_tt(),
+            stop: Arc::new(AtomicBool::new(false)),
+            deadline: None,
+            polls: 0,
+            stopped: false,
+            book: None,
+            database: None,
+            tablebase: None,
+            orderer: Box::new(ordering::ThreatFirst),
+            victor: None,
+        }
+    }
 
-        let (s, p) = negamax(pos, med, med + 1, &mut tt);
-        positions_searched += p;
+    // flag that stops the current search when set, can be shared with other threads
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
 
-        if s <= med {max = s}
-        else {min = s}
+    // searches stop once this time has passed, none to search until done
+    pub fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    // an opening book to look positions up in, none to search everything
+    pub fn set_book(&mut self, book: Option<Arc<pons::Book>>) {
+        self.book = book;
+    }
+
+    // a database of solved positions to look positions up in, none to search everything
+    pub fn set_database(&mut self, database: Option<Arc<database::Database>>) {
+        self.database = database;
+    }
+
+    // an endgame tablebase to look positions up in, none to search everything
+    pub fn set_tablebase(&mut self, tablebase: Option<Arc<database::Database>>) {
+        self.tablebase = tablebase;
+    }
+
+    // whether to try proving positions with the rules of victor before searching them
+    pub fn set_victor(&mut self, enabled: bool) {
+        self.victor = if enabled { Some(victor::Prover::new()) } else { None };
+    }
+
+    // changes how moves are ordered, see src/ordering.rs
+    pub fn set_move_orderer(&mut self, orderer: Box<dyn MoveOrderer>) {
+        self.orderer = orderer;
+    }
+
+    // writes the transposition table to a file, see src/table.rs
+    pub fn save_table<P: AsRef<Path>>(&self, file_path: P) -> io::Result<()> {
+        table::save(&self.tt[..], file_path)
     }
-    (min, positions_searched)
-}
 
-// takes a position, does a negamax search, returns its score and how many positions were searched
-fn negamax(pos: &mut Position, mut alpha: i8, mut beta: i8, tt: &mut Box<[u64; 1000003]>) -> (i8, u64) {
-
-    // use prior search if one exists
-    let hash = pos.hash();
-    let tt_record: u64 = tt[(hash % 1000003) as usize];
-    if hash == (tt_record & (2_u64.pow(49) - 1)) { // confirm record is for the position we are searching
-        let flag = tt_record >> 49 & 0b11;
-        let mut score: i8 = (tt_record >> 52 & 0b1111) as i8;
-        if (tt_record >> 51 & 0b1) == 1 { score *= -1 };
-        if flag == 0b00 { // lowerbound
-            if score > alpha { alpha = score }
-        } else if flag == 0b01 { // exact
-            return (score, 0);
-        } else { // upperbound
-            if score < beta { beta = score }
+    // replaces the transposition table with one saved earlier
+    pub fn load_table<P: AsRef<Path>>(&mut self, file_path: P) -> io::Result<()> {
+        self.tt = table::load(file_path)?;
+        Ok(())
+    }
+
+    // forget all previous searches
+    pub fn clear(&mut self) {
+        self.tt.fill(0);
+        self.orderer.clear();
+    }
+
+    // same as the score function, but none if the search was stopped
+    pub fn score(&mut self, pos: &mut Position) -> Option<(i8, u64)> {
+        let min = -(((42 - pos.moves.len()) / 2) as i8);
+        let max = ((43 - pos.moves.len()) / 2) as i8;
+        self.search(pos, min, max)
+    }
+
+    // only finds whether the position is a win (1), draw (0) or loss (-1), which is quicker to search
+    pub fn weak_score(&mut self, pos: &mut Position) -> Option<(i8, u64)> {
+        let (score, positions_searched) = self.search(pos, -1, 1)?;
+        Some((score.signum(), positions_searched))
+    }
+
+    // null window searches until the score is known to be in between min and max, or outside of them
+    fn search(&mut self, pos: &mut Position, mut min: i8, mut max: i8) -> Option<(i8, u64)> {
+        self.stopped = false;
+
+        // negamax never checks for a win this turn, since it always blocked the opponent one move earlier,
+        // but the position we are given might not have been searched into
+        if (0..7).any(|col| pos.is_legal_move(col) && pos.is_winning_move(col)) {
+            return Some((((43 - pos.moves.len()) / 2) as i8, 1))
         }
-        if alpha >= beta { return (alpha, 0) };
+
+        // null window search window = [alpha, alpha + 1]
+        // two return options, score > alpha or score <= alpha
+        // by using a small window, we quickly determine if the true score is better or worse than alpha
+        // we then adjust our window towards to direction of the true score
+
+        // counterintuitively these many searches are faster than a single search due to the small window resulting
+        // in a lot of pruning, and the transposition table helps us not repeat searches
+
+        let mut positions_searched: u64 = 0;
+
+        while min < max {
+            let mut med: i8 = min + (max - min) / 2;
+            if med <= 0 && min / 2 < med {med = min / 2}
+            else if med >= 0 && max / 2 > med {med = max / 2}
+
+            let (s, p) = self.negamax(pos, med, med + 1);
+            positions_searched += p;
+            if self.stopped { return None }
+
+            if s <= med {max = s}
+            else {min = s}
+        }
+        Some((min, positions_searched))
     }
 
-    // track original alpha for storing in transposition table
-    let original_alpha = alpha;
+    // same as the analyze function, but none if the search was stopped
+    pub fn analyze(&mut self, pos: &mut Position) -> Option<[Option<i8>; 7]> {
+        let mut scores = [None; 7];
 
-    // track # positions searched
-    // unnecessary for scoring a position, but useful for tracking progress
-    let mut total_positions: u64 = 1;
+        for (col, col_score) in scores.iter_mut().enumerate() {
+            if !pos.is_legal_move(col) { continue }
+            if pos.is_winning_move(col) {
+                *col_score = Some(((43 - pos.moves.len()) / 2) as i8);
+            } else {
+                pos.make_move(col);
+                let result = self.score(pos);
+                pos.undo_move();
+                *col_score = Some(-result?.0);
+            }
+        }
+        Some(scores)
+    }
 
-    // check if game is a tie
-    if pos.moves.len() == 42 {return (0, total_positions)};
+    // the moves of a perfect game from the position on, none if the search was stopped. it follows
+    // the best moves remembered in the transposition table, and only searches where the table does
+    // not say which move keeps the score
+    pub fn principal_variation(&mut self, pos: &Position) -> Option<Vec<usize>> {
+        let pos = &mut pos.clone();
+        let (mut score, _) = self.score(pos)?;
+        let mut line = Vec::new();
+        while pos.moves.len() < 42 {
+            if let Some(col) = (0..7).find(|&col| pos.is_legal_move(col) && pos.is_winning_move(col)) {
+                line.push(col);
+                break
+            }
 
-    let mut move_options = [3, 2, 4, 1, 5, 0, 6];
+            // a move stored with an exact score or lower bound equal to the score gets the score, so it
+            // can be played without searching
+            let hash = pos.hash();
+            let entry = self.probe(hash);
+            let remembered = entry.and_then(|(_, _, mv)| mv).map(|mv| orient(pos, hash, mv)).filter(|&col| pos.is_legal_move(col));
+            let col = match remembered.filter(|_| matches!(entry, Some((0b00 | 0b01, s, _)) if s == score)) {
+                Some(col) => col,
+                None => {
+                    // otherwise the remembered move and then the rest are checked with a null window
+                    // search, since no move can do better than the score
+                    let mut best = None;
+                    for col in remembered.into_iter().chain([3, 2, 4, 1, 5, 0, 6]) {
+                        if !pos.is_legal_move(col) { continue }
+                        pos.make_move(col);
+                        let result = self.search(pos, -score, -score + 1);
+                        pos.undo_move();
+                        if result?.0 <= -score {
+                            best = Some(col);
+                            break
+                        }
+                    }
+                    best.unwrap()
+                },
+            };
+            line.push(col);
+            pos.make_move(col);
+            score = -score;
+        }
+        Some(line)
+    }
 
-    // get threats for various reasons
-    let threats = get_threats(pos.board, 1 - pos.turn);
-    let live_threats = pos.get_live_threats(threats);
+    // checks the stop flag and the deadline every 4096 calls
+    fn should_stop(&mut self) -> bool {
+        self.polls += 1;
+        if self.polls.is_multiple_of(4096) {
+            let late = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            self.stopped = late || self.stop.load(AtomicOrdering::Relaxed);
+        }
+        self.stopped
+    }
 
-    // check if the position is a loss on opponents next turn (since we cannot win on this turn)
-    if pos.is_losing_position(threats, live_threats) { 
-        return ((-42 + pos.moves.len() as i8) / 2, total_positions) 
+    // the entry stored for a position, none if neither slot of its bucket holds it
+    pub(crate) fn entry(&self, hash: u64) -> Option<u64> {
+        bucket_entry(&self.tt[..], hash)
     }
 
-    // beta should be <= the max possible score
-    let max_possible_score: i8 = (41 - pos.moves.len() as i8) / 2;
-    if beta > max_possible_score {
-        beta = max_possible_score;
-        if alpha >= beta { return (beta, total_positions) } // alpha beta window is empty
+    // the flag, score and best move stored for a position, none if neither entry of its bucket holds it
+    fn probe(&self, hash: u64) -> Option<(u64, i8, Option<usize>)> {
+        self.entry(hash).map(decode)
     }
 
-    // if there is a must play move, it is our only option
-    let must_play_move = pos.must_play_move(live_threats);
-    if must_play_move < 7 {
-        // ideally we could update move options to just have the must play move, but 
-        // when move options is a vec! it is much slower than when it is an array
-        pos.make_move(must_play_move);
-        let (s, p) = negamax(pos, -1 * beta, -1 * alpha, tt);
-        pos.undo_move();
-        total_positions += p;
-        alpha = -1 * s
-    } else {
-        // sort moves to optimize pruning
-        let move_options = sort_moves(move_options, &pos);
+    // stores an entry in its bucket, in the effort preferred slot if it took at least as much effort
+    // as the entry there (which moves to the always replace slot), otherwise in the always replace slot
+    pub(crate) fn store(&mut self, hash: u64, into_tt: u64) {
+        bucket_store(&mut self.tt[..], hash, into_tt)
+    }
 
-        // search all legal moves 
-        for mv in move_options {
-            if pos.is_legal_move(mv) && !pos.is_losing_move(mv, threats) {
+    // takes a position, does a negamax search, returns its score and how many positions were searched
+    fn negamax(&mut self, pos: &mut Position, mut alpha: i8, mut beta: i8) -> (i8, u64) {
+
+        // give up without storing anything, the result would not mean anything
+        if self.should_stop() { return (0, 0) }
+
+        // use prior search if one exists
+        let hash = pos.hash();
+        let mut tt_move = None;
+        if let Some((flag, score, mv)) = self.probe(hash) {
+            tt_move = mv.map(|mv| orient(pos, hash, mv));
+            if flag == 0b00 { // lowerbound
+                if score > alpha { alpha = score }
+            } else if flag == 0b01 { // exact
+                return (score, 0);
+            } else { // upperbound
+                if score < beta { beta = score }
+            }
+            if alpha >= beta { return (alpha, 0) };
+        }
+
+        // positions in the opening book, the database or the tablebase are already solved
+        if let Some(score) = self.book.as_ref().and_then(|book| book.get(pos)) { return (score, 0) }
+        if let Some(score) = self.database.as_ref().and_then(|database| database.get(pos)) { return (score, 0) }
+        if let Some(score) = self.tablebase.as_ref().and_then(|tablebase| tablebase.get(pos)) { return (score, 0) }
+
+        // track original alpha for storing in transposition table
+        let original_alpha = alpha;
+
+        // track # positions searched
+        // unnecessary for scoring a position, but useful for tracking progress
+        let mut total_positions: u64 = 1;
+
+        // check if game is a tie
+        if pos.moves.len() == 42 {return (0, total_positions)};
+
+        let move_options = [3, 2, 4, 1, 5, 0, 6];
+
+        // get threats for various reasons
+        let threats = pos.threats(pos.board, 1 - pos.turn);
+        let live_threats = pos.get_live_threats(threats);
+
+        // check if the position is a loss on opponents next turn (since we cannot win on this turn)
+        if pos.is_losing_position(threats, live_threats) { 
+            return ((-42 + pos.moves.len() as i8) / 2, total_positions) 
+        }
+
+        // beta should be <= the max possible score
+        let max_possible_score: i8 = (41 - pos.moves.len() as i8) / 2;
+        if beta > max_possible_score {
+            beta = max_possible_score;
+            if alpha >= beta { return (beta, total_positions) } // alpha beta window is empty
+        }
+
+        // some positions are decided by zugzwang, which bounds the score without searching
+        if let Some(bound) = zugzwang::upper_bound(pos) {
+            if beta > bound {
+                beta = bound;
+                if alpha >= beta { return (beta, total_positions) }
+            }
+        }
+        if let Some(prover) = self.victor.as_mut().filter(|_| beta > -1) {
+            if let Some(bound) = prover.prove(pos).map(|proof| proof.bound()) {
+                if beta > bound {
+                    beta = bound;
+                    if alpha >= beta { return (beta, total_positions) }
+                }
+            }
+        }
+
+        // the move that raised alpha last, stored to be searched first next time
+        let mut best_move = None;
+
+        // if there is a must play move, it is our only option
+        let must_play_move = pos.must_play_move(live_threats);
+        if must_play_move < 7 {
+            // ideally we could update move options to just have the must play move, but 
+            // when move options is a vec! it is much slower than when it is an array
+            pos.make_move(must_play_move);
+            let (s, p) = self.negamax(pos, -beta, -alpha);
+            pos.undo_move();
+            total_positions += p;
+            alpha = -s;
+            best_move = Some(must_play_move);
+        } else {
+            // enhanced transposition cutoff: if a move leads to a position already known to be at most
+            // -beta for the opponent, it is good enough for a cutoff without searching anything
+            for mv in move_options {
+                if pos.is_legal_move(mv) && !pos.is_losing_move(mv, threats) {
+                    pos.make_move(mv);
+                    let child = self.probe(pos.hash());
+                    pos.undo_move();
+                    if let Some((0b01 | 0b10, s, _)) = child { // exact or upperbound
+                        if -s >= beta {
+                            self.orderer.cutoff(pos, mv);
+                            return (-s, total_positions)
+                        }
+                    }
+                }
+            }
+
+            // the best move from an earlier search of this position is searched first,
+            // and if it gives a cutoff the other moves never need to be sorted
+            let first = tt_move.filter(|&mv| pos.is_legal_move(mv) && !pos.is_losing_move(mv, threats));
+            if let Some(mv) = first {
                 pos.make_move(mv);
-                let (mut s, p) = negamax(pos, -1 * beta, -1 * alpha, tt);
+                let (s, p) = self.negamax(pos, -beta, -alpha);
                 pos.undo_move();
-                s *= -1; 
                 total_positions += p;
-                if s > alpha { alpha = s };
-                if alpha >= beta { break }
+                if -s > alpha {
+                    alpha = -s;
+                    best_move = Some(mv);
+                }
+                if alpha >= beta && !self.stopped { self.orderer.cutoff(pos, mv) }
+            }
+
+            if alpha < beta && !self.stopped {
+                // sort moves to optimize pruning
+                let move_options = self.orderer.order(move_options, pos);
+
+                // search all legal moves 
+                for mv in move_options {
+                    if Some(mv) != first && pos.is_legal_move(mv) && !pos.is_losing_move(mv, threats) {
+                        pos.make_move(mv);
+                        let (mut s, p) = self.negamax(pos, -beta, -alpha);
+                        pos.undo_move();
+                        s = -s; 
+                        total_positions += p;
+                        if self.stopped { break }
+                        if s > alpha {
+                            alpha = s;
+                            best_move = Some(mv);
+                        }
+                        if alpha >= beta {
+                            self.orderer.cutoff(pos, mv);
+                            break
+                        }
+                    }
+                }
             }
         }
+        if self.stopped { return (0, total_positions) }
+
+        // store score in transposition table for lookup if position is searched again
+        let effort = (64 - total_positions.leading_zeros() as u64) / 2;
+        self.store(hash, tt_entry(pos, hash, original_alpha, alpha, beta, effort, best_move));
+
+        (alpha, total_positions)
     }
+}
 
-    // store score in transposition table for lookup if position is searched again
+// packs the result of searching a position with an alpha beta window into a table entry, see create_tt
+pub(crate) fn tt_entry(pos: &Position, hash: u64, original_alpha: i8, alpha: i8, beta: i8, effort: u64, best_move: Option<usize>) -> u64 {
     let mut into_tt: u64 = hash; // record key
     if alpha < 0 { into_tt |= 1 << 51 } // record sign
-    into_tt |= (alpha.abs() as u64) << 52;// record abs of score
+    into_tt |= (alpha.unsigned_abs() as u64) << 52;// record abs of score
 
     if alpha <= original_alpha { // upperbound
         into_tt |= 0b10 << 49;
@@ -270,28 +661,74 @@ fn negamax(pos: &mut Position, mut alpha: i8, mut beta: i8, tt: &mut Box<[u64; 1
     } else { // exact
         into_tt |= 0b01 << 49;
     }
-    tt[(hash % 1000003) as usize] = into_tt; // store the value
+    into_tt |= effort.min(15) << 57; // record effort
+    if let Some(mv) = best_move.filter(|_| pos.rules == Rules::Standard) {
+        into_tt |= (orient(pos, hash, mv) as u64 + 1) << 61; // record best move
+    }
+    into_tt
+}
 
-    (alpha, total_positions)
+// unpacks the flag, score and best move of a table entry, see create_tt
+pub(crate) fn decode(tt_record: u64) -> (u64, i8, Option<usize>) {
+    let flag = tt_record >> 49 & 0b11;
+    let mut score: i8 = (tt_record >> 52 & 0b11111) as i8;
+    if (tt_record >> 51 & 0b1) == 1 { score *= -1 };
+    let mv = (tt_record >> 61).checked_sub(1).map(|mv| mv as usize);
+    (flag, score, mv)
+}
+
+// turns a move in a position into the same move for the position its hash was made from, or back again.
+// only used for regular connect 4, where the hash is either the position or its mirror image
+pub(crate) fn orient(pos: &Position, hash: u64, mv: usize) -> usize {
+    if pos.key() == hash { mv } else { 6 - mv }
+}
+
+// the entry stored for a position in a table made by create_tt, see Solver::entry
+pub(crate) fn bucket_entry(tt: &[u64], hash: u64) -> Option<u64> {
+    let bucket = tt_bucket(hash);
+    // confirm record is for the position we are searching
+    tt[bucket..bucket + 2].iter().find(|&&record| record & (2_u64.pow(49) - 1) == hash).copied()
+}
+
+// stores an entry in a table made by create_tt, see Solver::store
+pub(crate) fn bucket_store(tt: &mut [u64], hash: u64, into_tt: u64) {
+    let bucket = tt_bucket(hash);
+    let kept = tt[bucket];
+    if kept & (2_u64.pow(49) - 1) == hash || into_tt >> 57 & 0b1111 >= kept >> 57 & 0b1111 {
+        if kept & (2_u64.pow(49) - 1) != hash { tt[bucket + 1] = kept }
+        tt[bucket] = into_tt;
+    } else {
+        tt[bucket + 1] = into_tt;
+    }
+}
+
+// index of the first entry of a position's bucket. 499979 is the largest prime number of buckets
+// that fit in the table, for the same reason the table size is prime
+fn tt_bucket(hash: u64) -> usize {
+    (hash % 499979) as usize * 2
 }
 
 // gets the priority that a move should be searched
-fn move_priority(mv: usize, pos: &Position) -> u32 {
+pub(crate) fn move_priority(mv: usize, pos: &Position) -> u32 {
     let mut priority: u32 = 0;
     
     // prefer moves that create threats
     let mut board = pos.board;
     board[pos.turn] |= pos.get_col_height_mask(mv);
-    priority += get_threats(board, pos.turn).count_ones();
+    priority += pos.threats(board, pos.turn).count_ones();
 
     priority
 }
 
 // manually implementing insertion sort as it is most efficient for small lists, 
 // and it will reduce the number of calls to get_threats, which is somewhat expensive
-fn sort_moves(mut moves: [usize; 7], pos: &Position) -> [usize; 7] {
+pub(crate) fn sort_moves(moves: [usize; 7], pos: &Position) -> [usize; 7] {
     // reduces number of call to get_threats by storing each in memory
-    let mut scores = moves.map(|x| move_priority(x, pos));
+    sort_by_priority(moves, moves.map(|x| move_priority(x, pos)))
+}
+
+// sorts moves by their priorities, highest first, keeping the order of moves with the same priority
+pub(crate) fn sort_by_priority(mut moves: [usize; 7], mut scores: [u32; 7]) -> [usize; 7] {
 
     let mut i: usize = 1;
     while i < 7 {
@@ -312,17 +749,13 @@ fn sort_moves(mut moves: [usize; 7], pos: &Position) -> [usize; 7] {
 
 
 #[cfg(test)]
+#[allow(unused_mut, clippy::bool_assert_comparison)] // the original tests, written before clippy was part of the checks
 mod tests {
     use super::*;
 
     // returns start position
     fn start_position() -> Position {
-        Position {
-            board: [0, 0],
-            turn: 0,
-            moves: Vec::new(),
-            height_mask: 0b0000001000000100000010000001000000100000010000001
-        }
+        Position::new()
     }
 
     // takes a valid test position key and turns it into a position
@@ -335,50 +768,6 @@ mod tests {
         p
     }
 
-    // takes file path, tests score func on those position
-    // returns [% correct, avg solve time, avg # of positions searched]
-    fn check_progress<P: AsRef<Path>>(file_path: P) -> (f64, f64, u64) {
-        // read the desired file
-        let contents = match fs::read_to_string(&file_path) {
-            Ok(contents) => contents,
-            Err(e) => {
-                eprintln!("Error reading file: {}", e); 
-                return (-1.0, -1.0, 1)
-            },
-        };
-
-        let mut num_correct: f64 = 0.0;
-        let mut total_time = Duration::new(0, 0);
-        let mut total_num_positions: u64 = 0;
-
-        let mut line_count = 0;
-        //for each line, test the score func
-        for line in contents.lines() {
-            line_count += 1;
-            let mut parts = line.split_whitespace();
-            let key = parts.next().unwrap();
-            let actual_score: i8 = parts.next().unwrap().parse().expect("Not a valid number");
-
-            let mut p = key_to_position(key.to_string());
-            let start = SystemTime::now();
-            let (predicted_score, num_positions) = score(&mut p);
-            let end = SystemTime::now();
-            
-            let duration = end.duration_since(start).expect("Time went backwards");
-            if actual_score == predicted_score { 
-                num_correct += 1.0;
-                println!("{line_count}: {} {key}", duration.as_secs()); 
-            } else {
-                println!("INCORRECT {line_count}: {} {key} {actual_score} {predicted_score}", duration.as_secs());
-            };
-
-            total_time += duration;
-            total_num_positions += num_positions;
-        }
-        // return progress information
-        (num_correct / 10.0, (total_time.as_micros() / 1000) as f64 / 1_000_000.0, total_num_positions/ 1000)
-    }
-
     #[test]
     fn test_make_move_0() {
         let mut p = start_position();
@@ -490,6 +879,17 @@ mod tests {
         assert_eq!(p.is_winning_move(1), false);
     }
 
+    #[test]
+    fn test_from_moves_0() {
+        let p = Position::from_moves("4453").unwrap();
+        assert_eq!(p.moves, vec![3, 3, 4, 2]);
+        assert_eq!(p.num_moves(), 4);
+
+        assert!(Position::from_moves("448").is_err());
+        assert!(Position::from_moves("4444444").is_err());
+        assert!(Position::from_moves("1212121").is_err());
+    }
+
     #[test]
     fn test_key_to_position_0() {
         let p = key_to_position(String::from("111"));
@@ -535,6 +935,73 @@ mod tests {
         assert_eq!(s, 2);
     }
     
+    #[test]
+    fn test_score_5() { // player 1 can win immediately
+        let mut pos = key_to_position(String::from("121212"));
+        let (s, _p) = score(&mut pos);
+        assert_eq!(s, 18);
+    }
+
+    #[test]
+    fn test_tt_score_round_trip() {
+        // scores of 16 or more need all 5 bits of the score
+        for stored in [18_i8, -17, 16] {
+            let mut solver = Solver::new();
+            let mut pos = key_to_position(String::from("4455"));
+            let hash = pos.hash();
+            let sign = if stored < 0 { 1 << 51 } else { 0 };
+            solver.store(hash, hash | 0b01 << 49 | sign | (stored.unsigned_abs() as u64) << 52);
+            assert_eq!(solver.negamax(&mut pos, -21, 21), (stored, 0));
+        }
+    }
+
+    // remembers the cutoffs it is told about, as the # of moves played and the move
+    struct Cutoffs(Arc<std::sync::Mutex<Vec<(usize, usize)>>>);
+
+    impl MoveOrderer for Cutoffs {
+        fn order(&mut self, moves: [usize; 7], _pos: &Position) -> [usize; 7] {
+            moves
+        }
+
+        fn cutoff(&mut self, pos: &Position, mv: usize) {
+            self.0.lock().unwrap().push((pos.num_moves(), mv));
+        }
+    }
+
+    #[test]
+    fn test_table_cutoffs_reach_orderer() {
+        let cutoffs = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut pos = key_to_position(String::from("4455"));
+        let hash = pos.hash();
+
+        // the remembered best move, column 3, makes an open three and wins
+        let mut solver = Solver::new();
+        solver.set_move_orderer(Box::new(Cutoffs(Arc::clone(&cutoffs))));
+        solver.store(hash, tt_entry(&pos, hash, -21, -10, -10, 1, Some(2)));
+        assert!(solver.negamax(&mut pos, 0, 1).0 >= 1);
+        assert!(cutoffs.lock().unwrap().contains(&(4, 2)));
+
+        // column 1 leads to a position stored as a loss for the opponent, an enhanced transposition cutoff
+        cutoffs.lock().unwrap().clear();
+        let mut solver = Solver::new();
+        solver.set_move_orderer(Box::new(Cutoffs(Arc::clone(&cutoffs))));
+        pos.make_move(0);
+        let child = pos.hash();
+        solver.store(child, tt_entry(&pos, child, -10, -5, 10, 1, None));
+        pos.undo_move();
+        assert_eq!(solver.negamax(&mut pos, 0, 1), (5, 1));
+        assert_eq!(*cutoffs.lock().unwrap(), [(4, 0)]);
+    }
+
+    #[test]
+    fn test_analyze_0() {
+        let mut pos = key_to_position(String::from("2252576253462244111563365343671351441"));
+        let scores = analyze(&mut pos);
+        assert_eq!(scores.iter().flatten().max(), Some(&-1));
+        assert_eq!(scores[..5], [None; 5]);
+        assert!(scores[5].is_some() && scores[6].is_some());
+    }
+
     #[test]
     fn test_threats_0() { // horizontal
         let mut pos = start_position();
@@ -639,6 +1106,14 @@ mod tests {
 
         assert_eq!(pos.hash(), 0b0000001000000100000010000001000000100000010000010);
     }
+
+    #[test]
+    fn test_hash_3() {
+        // mirror images share a hash, even when the outer columns are the same height
+        for (moves, mirrored) in [("3", "5"), ("4312", "4576"), ("1737", "7151")] {
+            assert_eq!(Position::from_moves(moves).unwrap().hash(), Position::from_moves(mirrored).unwrap().hash());
+        }
+    }
     
     #[test]
     fn test_sort_moves_0() {
@@ -673,8 +1148,55 @@ mod tests {
     }
 
     #[test]
-    fn test_progress_check() { // used to check efficiency progress, will not pass
-        let result = check_progress("test_files/Start-Hard.txt");
-        assert_eq!(result, (0.0, 0.0, 0));
-    }    
+    fn test_tt_replacement() {
+        let mut solver = Solver::new();
+        // three positions in the same bucket, stored with effort 5, 2 and 9
+        let (a, b, c) = (3, 3 + 499979, 3 + 2 * 499979);
+        solver.store(a, a | 0b01 << 49 | 4 << 52 | 5 << 57);
+        solver.store(b, b | 0b01 << 49 | 1 << 51 | 7 << 52 | 2 << 57);
+        assert_eq!(solver.probe(a), Some((0b01, 4, None)));
+        assert_eq!(solver.probe(b), Some((0b01, -7, None)));
+
+        // more effort takes the first slot, and what was there moves to the second
+        solver.store(c, c | 0b10 << 49 | 18 << 52 | 9 << 57);
+        assert_eq!(solver.probe(c), Some((0b10, 18, None)));
+        assert_eq!(solver.probe(a), Some((0b01, 4, None)));
+        assert_eq!(solver.probe(b), None);
+
+        // less effort only replaces the second slot
+        solver.store(b, b | 2 << 52 | 1 << 57); // lowerbound
+        assert_eq!(solver.probe(c), Some((0b10, 18, None)));
+        assert_eq!(solver.probe(b), Some((0b00, 2, None)));
+        assert_eq!(solver.probe(a), None);
+    }
+
+    #[test]
+    fn test_principal_variation() {
+        for moves in ["2252576253462244111563365343671351441", "52753311433677442422121"] {
+            let pos = Position::from_moves(moves).unwrap();
+            let mut solver = Solver::new();
+            let line = solver.principal_variation(&pos).unwrap();
+
+            // every move keeps the score, and the game ends with a win or a full board
+            let mut played = pos.clone();
+            let (mut expected, _) = score(&mut played.clone());
+            for &col in &line[..line.len() - 1] {
+                assert!(!played.is_winning_move(col));
+                played.make_move(col);
+                let (s, _) = score(&mut played.clone());
+                assert_eq!(s, -expected, "{moves}");
+                expected = s;
+            }
+            let last = *line.last().unwrap();
+            assert!(played.is_winning_move(last) || played.moves.len() == 41);
+        }
+    }
+
+    #[test]
+    fn test_best_move_is_mirrored() {
+        let p = Position::from_moves("1").unwrap();
+        let mirrored = Position::from_moves("7").unwrap();
+        assert_eq!(p.hash(), mirrored.hash());
+        assert_eq!(orient(&mirrored, mirrored.hash(), orient(&p, p.hash(), 1)), 5);
+    }
 }
@@ -0,0 +1,216 @@
+// monte carlo tree search (uct), an alternative player for when solving a position with negamax
+// is not feasible, like the cylinder variant. instead of searching every branch, it plays many
+// semi-random games and spends more of its time on the moves that keep winning those games
+
+use rand::prelude::*;
+use crate::Position;
+
+pub struct Mcts {
+    pub iterations: u32, // number of games to play out per move
+    pub exploration: f64, // how much to favour rarely visited moves over moves with a good record
+    pub seed: Option<u64>, // fixed seed for reproducible moves, random if none
+}
+
+impl Default for Mcts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Node {
+    parent: usize, // the root is its own parent
+    mv: usize, // move that led to this node
+    children: Vec<usize>,
+    untried: Vec<usize>, // moves that do not have a node yet
+    visits: f64,
+    reward: f64, // total reward for the player who played mv (1 for a win, 0.5 for a tie)
+    result: Option<f64>, // reward for the player who played mv if the game ended there
+}
+
+impl Mcts {
+
+    pub fn new() -> Mcts {
+        Mcts { iterations: 10_000, exploration: 2_f64.sqrt(), seed: None }
+    }
+
+    // picks a move for the player to play (assumes the game is not over)
+    pub fn best_move(&self, pos: &mut Position) -> usize {
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let mut tree = vec![Node {
+            parent: 0,
+            mv: 7,
+            children: Vec::new(),
+            untried: candidate_moves(pos),
+            visits: 0.0,
+            reward: 0.0,
+            result: None,
+        }];
+
+        for _ in 0..self.iterations {
+            let mut node = 0;
+            let mut depth = 0;
+
+            // selection, walk down fully expanded nodes
+            while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+                node = self.select_child(&tree, node);
+                pos.make_move(tree[node].mv);
+                depth += 1;
+            }
+
+            // expansion, add a node for one untried move
+            if !tree[node].untried.is_empty() {
+                let i = rng.gen_range(0..tree[node].untried.len());
+                let mv = tree[node].untried.swap_remove(i);
+                let result = if pos.is_winning_move(mv) {
+                    Some(1.0)
+                } else if pos.moves.len() == 41 {
+                    Some(0.5)
+                } else {
+                    None
+                };
+                pos.make_move(mv);
+                depth += 1;
+
+                let untried = if result.is_none() { candidate_moves(pos) } else { Vec::new() };
+                tree.push(Node { parent: node, mv, children: Vec::new(), untried, visits: 0.0, reward: 0.0, result });
+                let child = tree.len() - 1;
+                tree[node].children.push(child);
+                node = child;
+            }
+
+            // simulation, the rollout reward is for the player to move, so flip it for the player who moved here
+            let mut reward = match tree[node].result {
+                Some(r) => r,
+                None => 1.0 - rollout(pos, &mut rng),
+            };
+
+            // backpropagation, alternating perspective on the way up
+            loop {
+                tree[node].visits += 1.0;
+                tree[node].reward += reward;
+                if node == 0 { break }
+                reward = 1.0 - reward;
+                node = tree[node].parent;
+            }
+
+            for _ in 0..depth {
+                pos.undo_move();
+            }
+        }
+
+        // the most visited move is the most reliable one. with no iterations nothing was visited, so
+        // fall back to the first move worth considering
+        match tree[0].children.iter().max_by(|&&a, &&b| tree[a].visits.total_cmp(&tree[b].visits)) {
+            Some(&best) => tree[best].mv,
+            None => candidate_moves(pos)[0],
+        }
+    }
+
+    // picks the child with the best upper confidence bound
+    fn select_child(&self, tree: &[Node], node: usize) -> usize {
+        let log_visits = tree[node].visits.ln();
+        let uct = |child: usize| {
+            let n = &tree[child];
+            n.reward / n.visits + self.exploration * (log_visits / n.visits).sqrt()
+        };
+        *tree[node].children.iter().max_by(|&&a, &&b| uct(a).total_cmp(&uct(b))).unwrap()
+    }
+}
+
+// moves worth considering: a win if there is one, otherwise block the opponents live threat,
+// otherwise anything that does not play directly under an opponent threat
+fn candidate_moves(pos: &Position) -> Vec<usize> {
+    let legal: Vec<usize> = (0..7).filter(|&col| pos.is_legal_move(col)).collect();
+    if let Some(&mv) = legal.iter().find(|&&col| pos.is_winning_move(col)) { return vec![mv] }
+
+    let threats = pos.threats(pos.board, 1 - pos.turn);
+    let must_play_move = pos.must_play_move(pos.get_live_threats(threats));
+    if must_play_move < 7 { return vec![must_play_move] }
+
+    let safe: Vec<usize> = legal.iter().copied().filter(|&col| !pos.is_losing_move(col, threats)).collect();
+    if safe.is_empty() { legal } else { safe }
+}
+
+// plays random candidate moves until the game ends, returns the reward for the player to move
+fn rollout(pos: &mut Position, rng: &mut StdRng) -> f64 {
+    let mut played = 0;
+    let mut reward = 0.5;
+
+    while pos.moves.len() < 42 {
+        let mv = *candidate_moves(pos).choose(rng).unwrap();
+        if pos.is_winning_move(mv) {
+            reward = if played % 2 == 0 { 1.0 } else { 0.0 };
+            break
+        }
+        pos.make_move(mv);
+        played += 1;
+    }
+
+    for _ in 0..played {
+        pos.undo_move();
+    }
+    reward
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rules;
+
+    fn seeded(iterations: u32) -> Mcts {
+        Mcts { iterations, exploration: 2_f64.sqrt(), seed: Some(27) }
+    }
+
+    #[test]
+    fn test_takes_win() {
+        let mut p = Position::new();
+        p.make_moves(vec![3, 3, 2, 2, 4, 4]);
+        assert!([1, 5].contains(&seeded(200).best_move(&mut p)));
+    }
+
+    #[test]
+    fn test_blocks_threat() {
+        let mut p = Position::new();
+        p.make_moves(vec![0, 3, 0, 3, 6, 3]);
+        assert_eq!(seeded(200).best_move(&mut p), 3);
+    }
+
+    #[test]
+    fn test_takes_wrapping_win() {
+        let mut p = Position::with_rules(Rules::Cylinder);
+        p.make_moves(vec![5, 5, 6, 6, 0, 0]);
+        assert_eq!(seeded(200).best_move(&mut p), 1);
+    }
+
+    #[test]
+    fn test_no_iterations() {
+        let mut p = Position::new();
+        p.make_moves(vec![3, 3, 2, 2, 4, 4]);
+        assert!([1, 5].contains(&seeded(0).best_move(&mut p)));
+        assert!(Position::new().is_legal_move(seeded(0).best_move(&mut Position::new())));
+    }
+
+    #[test]
+    fn test_seed_is_deterministic() {
+        let mut p = Position::new();
+        p.make_moves(vec![3, 3, 2]);
+        let mcts = seeded(500);
+        let mv = mcts.best_move(&mut p);
+        for _ in 0..3 {
+            assert_eq!(mcts.best_move(&mut p), mv);
+        }
+        assert_eq!(p.moves, vec![3, 3, 2]);
+    }
+
+    #[test]
+    fn test_finds_forced_win() {
+        // playing 3 makes two threats on the bottom row that cannot both be blocked
+        let mut p = Position::new();
+        p.make_moves(vec![2, 2, 4, 4]);
+        assert_eq!(seeded(2000).best_move(&mut p), 3);
+    }
+}
@@ -0,0 +1,179 @@
+// move ordering strategies for negamax. the solver searches moves in whatever order its orderer
+// gives, so a new idea for ordering moves can be tried by adding an orderer here and comparing
+// it with the others in the bench binary (--ordering <name>), without touching negamax.
+//
+// orderings so far:
+//   center    center columns first (version 3)
+//   threats   moves that make the most threats first, then center first (versions 9.1 and 9.2, the default)
+//   history   like threats, but killer moves and the history heuristic break ties instead of the center
+//   learned   like threats, but ties go to the spot best moves landed on most often in a set of solved positions
+
+use crate::bench::{read_test_file, TestCase};
+use crate::{cli, move_priority, sort_by_priority, sort_moves, Position, Solver};
+
+pub trait MoveOrderer: Send {
+    // the moves to search, given in center first order, sorted with the most promising first
+    fn order(&mut self, moves: [usize; 7], pos: &Position) -> [usize; 7];
+
+    // called when a move gave a cutoff, for orderings that learn while searching
+    fn cutoff(&mut self, _pos: &Position, _mv: usize) {}
+
+    // forget anything learned while searching
+    fn clear(&mut self) {}
+}
+
+pub struct CenterFirst;
+
+impl MoveOrderer for CenterFirst {
+    fn order(&mut self, moves: [usize; 7], _pos: &Position) -> [usize; 7] {
+        moves
+    }
+}
+
+pub struct ThreatFirst;
+
+impl MoveOrderer for ThreatFirst {
+    fn order(&mut self, moves: [usize; 7], pos: &Position) -> [usize; 7] {
+        sort_moves(moves, pos)
+    }
+}
+
+pub struct History {
+    killers: [[usize; 2]; 42], // the last two moves that gave a cutoff at each ply, 7 for none
+    history: [[u32; 7]; 42], // how much searching cutoffs by each move at each ply have saved
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> History {
+        History { killers: [[7; 2]; 42], history: [[0; 7]; 42] }
+    }
+}
+
+impl MoveOrderer for History {
+    // among moves that make as many threats, killer moves come first and then the moves with the most history
+    fn order(&mut self, moves: [usize; 7], pos: &Position) -> [usize; 7] {
+        let ply = pos.num_moves();
+        let priorities = moves.map(|mv| {
+            let killer = if self.killers[ply].contains(&mv) { 1 << 15 } else { 0 };
+            move_priority(mv, pos) << 16 | killer | self.history[ply][mv].min((1 << 15) - 1)
+        });
+        sort_by_priority(moves, priorities)
+    }
+
+    // more history the closer to the root the cutoff was, since those cutoffs save more searching
+    fn cutoff(&mut self, pos: &Position, mv: usize) {
+        let ply = pos.num_moves();
+        if self.killers[ply][0] != mv { self.killers[ply] = [mv, self.killers[ply][0]] }
+        let remaining = (42 - ply) as u32;
+        self.history[ply][mv] = self.history[ply][mv].saturating_add(remaining * remaining);
+    }
+
+    fn clear(&mut self) {
+        *self = History::new();
+    }
+}
+
+#[derive(Clone)]
+pub struct Learned {
+    weights: [u32; 49], // how often a best move landed on each spot of the bitboard
+}
+
+impl Learned {
+    // learns from solved positions, finding every best move of each of them
+    pub fn train(cases: &[TestCase]) -> Result<Learned, String> {
+        let mut weights = [0; 49];
+        let mut solver = Solver::new();
+        for case in cases {
+            let mut pos = Position::from_moves(&case.moves).map_err(|e| format!("invalid position {}: {e}", case.moves))?;
+            let scores = solver.analyze(&mut pos).unwrap();
+            let best = scores.iter().flatten().max();
+            for col in (0..7).filter(|&col| scores[col].is_some() && scores[col].as_ref() == best) {
+                weights[pos.get_col_height_mask(col).trailing_zeros() as usize] += 1;
+            }
+        }
+        Ok(Learned { weights })
+    }
+}
+
+impl MoveOrderer for Learned {
+    fn order(&mut self, moves: [usize; 7], pos: &Position) -> [usize; 7] {
+        let priorities = moves.map(|mv| {
+            let spot = pos.get_col_height_mask(mv).trailing_zeros() as usize;
+            move_priority(mv, pos) << 16 | self.weights[spot].min(u16::MAX as u32)
+        });
+        sort_by_priority(moves, priorities)
+    }
+}
+
+// makes new orderers of the ordering with the given name, learned:<test file> trains on a test
+// file (a path, or a name in test_files/ like End-Easy) once and copies what it learned
+pub fn by_name(name: &str) -> Result<Box<dyn Fn() -> Box<dyn MoveOrderer>>, String> {
+    match name {
+        "center" => Ok(Box::new(|| Box::new(CenterFirst))),
+        "threats" => Ok(Box::new(|| Box::new(ThreatFirst))),
+        "history" => Ok(Box::new(|| Box::new(History::new()))),
+        _ => {
+            let Some(file) = name.strip_prefix("learned:") else { return Err(format!("unknown ordering {name}")) };
+            let path = cli::test_file(file);
+            let cases = read_test_file(&path).map_err(|e| format!("Error reading {}: {e}", path.display()))?;
+            let learned = Learned::train(&cases)?;
+            Ok(Box::new(move || Box::new(learned.clone())))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::score;
+
+    #[test]
+    fn test_history_order() {
+        let mut p = Position::new();
+        p.make_moves(vec![2, 2, 3, 3]);
+        let mut history = History::new();
+        history.history[4][1] = 50;
+        history.history[4][5] = 10;
+        history.cutoff(&p, 6);
+
+        // threats still come first, and the killer move beats history
+        assert_eq!(history.order([3, 2, 4, 1, 5, 0, 6], &p), [1, 4, 5, 0, 6, 3, 2]);
+        assert_eq!(history.history[4][6], 38 * 38);
+
+        history.clear();
+        assert_eq!(history.order([3, 2, 4, 1, 5, 0, 6], &p), ThreatFirst.order([3, 2, 4, 1, 5, 0, 6], &p));
+    }
+
+    #[test]
+    fn test_learned_order() {
+        let cases = read_test_file("test_files/End-Easy.txt").unwrap();
+        let mut learned = Learned::train(&cases[..20]).unwrap();
+        assert!(learned.weights.iter().sum::<u32>() >= 20);
+
+        // ties between moves that make as many threats go to the most common spot for a best move
+        let p = Position::new();
+        let bottom = learned.weights[..].chunks(7).map(|col| col[0]).collect::<Vec<u32>>();
+        let first = learned.order([3, 2, 4, 1, 5, 0, 6], &p)[0];
+        assert_eq!(bottom[first], *bottom.iter().max().unwrap());
+    }
+
+    #[test]
+    fn test_every_ordering_solves() {
+        for name in ["center", "threats", "history", "learned:End-Easy"] {
+            let make = by_name(name).unwrap();
+            let mut solver = Solver::new();
+            solver.set_move_orderer(make());
+            for moves in ["2252576253462244111563365343671351441", "7422341735647741166133573473242566", "274552224131661"] {
+                let mut p = Position::from_moves(moves).unwrap();
+                assert_eq!(solver.score(&mut p.clone()).unwrap().0, score(&mut p).0, "{name} {moves}");
+            }
+        }
+        assert!(by_name("sideways").is_err());
+    }
+}
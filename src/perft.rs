@@ -0,0 +1,108 @@
+// perft, a name borrowed from chess engines: counting every position reachable from a starting
+// position, ply by ply, to check move generation against known numbers.
+// a game stops when it is won, so positions where someone has just connected four are counted but
+// not played on from
+
+use std::collections::{HashMap, HashSet};
+use crate::{mirror, Position};
+
+// the number of positions reachable after each ply from the empty board, folding mirrored positions
+// together or not (https://oeis.org/A212693), 4,531,985,219,092 in total
+pub const KNOWN_POSITIONS: [u64; 43] = [
+    1, 7, 49, 238, 1120, 4263, 16422, 54859, 184275, 558186, 1662623, 4568683, 12236101, 30929111,
+    75437595, 176541259, 394591391, 858218743, 1763883894, 3568259802, 6746155945, 12673345045,
+    22010823988, 38263228189, 60830813459, 97266114959, 140728569039, 205289508055, 268057611944,
+    352626845666, 410378505447, 479206477733, 488906447183, 496636890702, 433471730336, 370947887723,
+    266313901222, 183615682381, 104004465349, 55156010773, 22695896495, 7811825938, 1459332899,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlyCount {
+    pub ply: usize, // moves played since the starting position
+    pub sequences: u64, // ways of playing that many moves
+    pub positions: u64, // different positions they lead to
+    pub folded: u64, // different positions, counting a position and its mirror image once
+}
+
+// counts from the starting position (ply 0) up to the given number of moves after it.
+// positions are kept a ply at a time, so memory grows with the number of positions at the widest ply
+pub fn perft(start: &Position, depth: usize) -> Vec<PlyCount> {
+    // each different position in the current ply, with the number of sequences that reach it
+    let mut frontier: HashMap<u64, (Position, u64)> = HashMap::from([(start.key(), (start.clone(), 1))]);
+    let mut counts = vec![PlyCount { ply: 0, sequences: 1, positions: 1, folded: 1 }];
+
+    for ply in 1..=depth {
+        let mut next: HashMap<u64, (Position, u64)> = HashMap::new();
+        let mut won: HashMap<u64, u64> = HashMap::new();
+        let mut folded: HashSet<u64> = HashSet::new();
+
+        for (pos, sequences) in frontier.into_values() {
+            for col in 0..7 {
+                if !pos.is_legal_move(col) { continue }
+                let winning = pos.is_winning_move(col);
+                let mut child = pos.clone();
+                child.make_move(col);
+                // the smaller of the two keys, so a position and its mirror image always fold together
+                folded.insert(child.key().min(mirror(child.key())));
+                if winning {
+                    *won.entry(child.key()).or_default() += sequences;
+                } else {
+                    next.entry(child.key()).or_insert((child, 0)).1 += sequences;
+                }
+            }
+        }
+
+        // a won position can only be reached by winning, so it is never in next as well
+        counts.push(PlyCount {
+            ply,
+            sequences: next.values().map(|(_, n)| n).sum::<u64>() + won.values().sum::<u64>(),
+            positions: (next.len() + won.len()) as u64,
+            folded: folded.len() as u64,
+        });
+        if next.is_empty() { break }
+        frontier = next;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_total() {
+        assert_eq!(KNOWN_POSITIONS.iter().sum::<u64>(), 4_531_985_219_092);
+    }
+
+    #[test]
+    fn test_start_position() {
+        let counts = perft(&Position::new(), 9);
+        for c in &counts {
+            assert_eq!(c.positions, KNOWN_POSITIONS[c.ply]);
+            assert!(c.folded < c.positions || c.ply == 0);
+            assert!(c.folded * 2 >= c.positions);
+        }
+
+        // nobody can have won yet, so every sequence of moves is a different game
+        for c in &counts[..=6] {
+            assert_eq!(c.sequences, 7_u64.pow(c.ply as u32));
+        }
+        assert!(counts[7].sequences < 7_u64.pow(7));
+    }
+
+    #[test]
+    fn test_folded() {
+        // the positions after one move are the 4 columns up to the mirror image
+        let counts = perft(&Position::new(), 2);
+        assert_eq!(counts[1].folded, 4);
+        assert_eq!(counts[2].folded, 25);
+    }
+
+    #[test]
+    fn test_near_end() {
+        // five empty spots in two columns, and counting stops once every game is over
+        let pos = Position::from_moves("2252576253462244111563365343671351441").unwrap();
+        let counts: Vec<(u64, u64)> = perft(&pos, 10).iter().map(|c| (c.sequences, c.positions)).collect();
+        assert_eq!(counts, [(1, 1), (2, 2), (3, 3), (1, 1), (1, 1)]);
+    }
+}
@@ -0,0 +1,292 @@
+// compatibility with Pascal Pons' solver (https://github.com/PascalPons/connect4), so scripts written
+// for it can run ours instead. his solver reads one position per line as moves numbered 1-7 and
+// prints the line followed by its score, which is the format of the test files. with -w it only
+// prints whether the position is a win (1), draw (0) or loss (-1), and with -a it prints the score of
+// every column instead, -1000 for a full one
+//
+// his opening book files (like 7x6.book) start with six bytes: width, height, the number of moves
+// the book covers, how many bytes of each key are kept, how many bytes each value takes (always 1)
+// and the log2 of the table size. then come all the partial keys, little endian, and then all the
+// values. the table size is the first prime >= 2^log_size, a position is stored at its key3 mod the
+// table size, and its value is its score + 19, with 0 meaning nothing is stored
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use crate::{Position, Rules, Solver};
+
+// printed by -a for a column that can not be played
+pub const INVALID_MOVE: i32 = -1000;
+
+// the lowest possible score, values in a book are stored as score - MIN_SCORE + 1
+const MIN_SCORE: i8 = -18;
+
+pub struct Book {
+    depth: usize, // positions with more moves than this are not in the book
+    key_bytes: usize, // how much of each key3 is kept
+    log_size: u8,
+    keys: Vec<u8>, // key_bytes per entry
+    values: Vec<u8>,
+}
+
+impl Book {
+
+    // empty book for positions of up to depth moves
+    pub fn new(depth: usize, key_bytes: usize, log_size: u8) -> Result<Book, String> {
+        let size = table_size(depth, key_bytes, log_size)?;
+        Ok(Book { depth, key_bytes, log_size, keys: vec![0; size * key_bytes], values: vec![0; size] })
+    }
+
+    pub fn read<P: AsRef<Path>>(file_path: P) -> io::Result<Book> {
+        Book::from_bytes(&fs::read(file_path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn write<P: AsRef<Path>>(&self, file_path: P) -> io::Result<()> {
+        fs::write(file_path, self.to_bytes())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Book, String> {
+        let [width, height, depth, key_bytes, value_bytes, log_size] = *bytes.get(..6).ok_or("book is missing its header")? else { unreachable!() };
+        if (width, height) != (7, 6) { return Err(format!("book is for a {width}x{height} board")) }
+        if value_bytes != 1 { return Err(format!("values can not be {value_bytes} bytes")) }
+
+        // the size is checked before making the book, so a bad header can not ask for a huge table
+        let size = table_size(depth as usize, key_bytes as usize, log_size)?;
+        let keys_end = 6 + size * key_bytes as usize;
+        if bytes.len() != keys_end + size {
+            return Err(format!("book should be {} bytes but is {}", keys_end + size, bytes.len()))
+        }
+        let mut book = Book::new(depth as usize, key_bytes as usize, log_size)?;
+        book.keys.copy_from_slice(&bytes[6..keys_end]);
+        book.values.copy_from_slice(&bytes[keys_end..]);
+        Ok(book)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![7, 6, self.depth as u8, self.key_bytes as u8, 1, self.log_size];
+        bytes.extend_from_slice(&self.keys);
+        bytes.extend_from_slice(&self.values);
+        bytes
+    }
+
+    // # of moves the book covers
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    // score of a position, none if it is not in the book
+    pub fn get(&self, pos: &Position) -> Option<i8> {
+        if pos.rules != Rules::Standard || pos.num_moves() > self.depth { return None }
+        let key = key3(pos);
+        let i = (key % self.values.len() as u64) as usize;
+        if self.values[i] == 0 || self.partial_key(i) != key & self.key_mask() { return None }
+        Some(self.values[i] as i8 + MIN_SCORE - 1)
+    }
+
+    // stores the score of a position, replacing whatever shared its slot
+    pub fn put(&mut self, pos: &Position, score: i8) {
+        let key = key3(pos);
+        let i = (key % self.values.len() as u64) as usize;
+        let partial = (key & self.key_mask()).to_le_bytes();
+        self.keys[i * self.key_bytes..(i + 1) * self.key_bytes].copy_from_slice(&partial[..self.key_bytes]);
+        self.values[i] = (score - MIN_SCORE + 1) as u8;
+    }
+
+    fn partial_key(&self, i: usize) -> u64 {
+        let mut bytes = [0; 8];
+        bytes[..self.key_bytes].copy_from_slice(&self.keys[i * self.key_bytes..(i + 1) * self.key_bytes]);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn key_mask(&self) -> u64 {
+        u64::MAX >> (64 - 8 * self.key_bytes)
+    }
+}
+
+// the key Pons' books are indexed by. each column is read from the bottom as base 3 digits,
+// 1 for the player to play and 2 for the opponent, followed by a 0. the lower of the key read left
+// to right and right to left is used, so mirrored positions share a key
+pub fn key3(pos: &Position) -> u64 {
+    let mask = pos.board[0] | pos.board[1];
+    let add_col = |key: &mut u64, col: usize| {
+        let mut spot: u64 = 1 << (7 * col);
+        while spot & mask != 0 {
+            *key = *key * 3 + if spot & pos.board[pos.turn] != 0 { 1 } else { 2 };
+            spot <<= 1;
+        }
+        *key *= 3;
+    };
+    let (mut forward, mut reverse) = (0, 0);
+    for col in 0..7 { add_col(&mut forward, col) }
+    for col in (0..7).rev() { add_col(&mut reverse, col) }
+    forward.min(reverse) / 3
+}
+
+// # of entries in a book, checking the header values first. pons' 7x6.book has a log size of 23, and
+// more than 32 would be a book of over 20gb
+fn table_size(depth: usize, key_bytes: usize, log_size: u8) -> Result<usize, String> {
+    if depth > 42 { return Err(format!("a book can not be {depth} moves deep")) }
+    if ![1, 2, 4].contains(&key_bytes) { return Err(format!("keys can not be {key_bytes} bytes")) }
+    if !(1..=32).contains(&log_size) { return Err(format!("log size {log_size} is out of range")) }
+    Ok(next_prime(1 << log_size) as usize)
+}
+
+// smallest prime >= n
+fn next_prime(mut n: u64) -> u64 {
+    let is_prime = |n: u64| n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| !n.is_multiple_of(d));
+    while !is_prime(n) { n += 1 }
+    n
+}
+
+// a line of -a output, the scores of all seven columns
+pub fn format_analysis(scores: &[Option<i8>; 7]) -> String {
+    let scores: Vec<String> = scores.iter().map(|s| s.map_or(INVALID_MOVE, i32::from).to_string()).collect();
+    scores.join(" ")
+}
+
+// reads a line of -a output back into its moves and column scores
+pub fn parse_analysis(line: &str) -> Result<(String, [Option<i8>; 7]), String> {
+    let mut parts: Vec<&str> = line.split_whitespace().collect();
+    // the start position has no moves, so its line is only the scores
+    if parts.len() == 7 { parts.insert(0, "") }
+    if parts.len() != 8 { return Err(format!("expected moves and 7 scores but found {} fields", parts.len())) }
+
+    let mut scores = [None; 7];
+    for (score, part) in scores.iter_mut().zip(&parts[1..]) {
+        match part.parse::<i32>() {
+            Ok(INVALID_MOVE) => (),
+            Ok(s) if i8::try_from(s).is_ok() => *score = Some(s as i8),
+            _ => return Err(format!("{part} is not a score")),
+        }
+    }
+    Ok((parts[0].to_string(), scores))
+}
+
+// solves one line of input the way Pons' solver does, giving the line to print
+pub fn solve_line(solver: &mut Solver, line: &str, weak: bool, analyze: bool) -> Result<String, String> {
+    let mut pos = Position::new();
+    if pos.play(line).is_err() {
+        return Err(format!("Invalid move {} \"{line}\"", pos.num_moves() + 1))
+    }
+
+    if !analyze {
+        let score = if weak { solver.weak_score(&mut pos) } else { solver.score(&mut pos) };
+        return Ok(format!("{line} {}", score.unwrap().0))
+    }
+
+    let mut scores = solver.analyze(&mut pos).unwrap();
+    if weak { scores = scores.map(|s| s.map(i8::signum)) }
+    Ok(format!("{line} {}", format_analysis(&scores)))
+}
+
+// answers every line of the input, invalid lines are reported on errors and skipped like Pons does
+pub fn run<R: BufRead, W: Write, E: Write>(input: R, mut output: W, mut errors: E, solver: &mut Solver, weak: bool, analyze: bool) -> io::Result<()> {
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        match solve_line(solver, line, weak, analyze) {
+            Ok(answer) => writeln!(output, "{answer}")?,
+            Err(e) => writeln!(errors, "Line {}: {e}", i + 1)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_key3() {
+        assert_eq!(key3(&Position::new()), 0);
+
+        // the stone belongs to the opponent once it has been played, and the right to left key is lower
+        let pos = Position::from_moves("1").unwrap();
+        assert_eq!(key3(&pos), 2);
+        assert_eq!(key3(&pos), key3(&Position::from_moves("7").unwrap()));
+        assert_ne!(key3(&pos), key3(&Position::from_moves("2").unwrap()));
+    }
+
+    #[test]
+    fn test_next_prime() {
+        assert_eq!(next_prime(1 << 23), 8388617);
+        assert_eq!(next_prime(13), 13);
+    }
+
+    #[test]
+    fn test_book_round_trip() {
+        let mut book = Book::new(4, 2, 10).unwrap();
+        let pos = Position::from_moves("4453").unwrap();
+        assert_eq!(book.get(&pos), None);
+        book.put(&pos, -3);
+        assert_eq!(book.get(&Position::from_moves("4453").unwrap()), Some(-3));
+        assert_eq!(book.get(&Position::from_moves("4435").unwrap()), Some(-3)); // mirror of 4453
+        assert_eq!(book.get(&Position::from_moves("4454").unwrap()), None);
+
+        let bytes = book.to_bytes();
+        assert_eq!(bytes[..6], [7, 6, 4, 2, 1, 10]);
+        assert_eq!(bytes.len(), 6 + 3 * 1031);
+        let read = Book::from_bytes(&bytes).unwrap();
+        assert_eq!(read.get(&pos), Some(-3));
+
+        // deeper positions are never looked up
+        let deep = Position::from_moves("44531").unwrap();
+        book.put(&deep, 2);
+        assert_eq!(book.get(&deep), None);
+
+        assert!(Book::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Book::from_bytes(&[8, 6, 4, 2, 1, 10]).is_err());
+        assert!(Book::new(4, 3, 10).is_err());
+
+        // a header asking for a huge table is turned down by its size, without making the table
+        assert_eq!(Book::from_bytes(&[7, 6, 4, 4, 1, 32]).err().unwrap(), "book should be 21474836561 bytes but is 6");
+        assert!(Book::from_bytes(&[7, 6, 4, 4, 1, 60]).err().unwrap().contains("out of range"));
+    }
+
+    #[test]
+    fn test_solver_uses_book() {
+        // a book that lies about a position is believed
+        let mut book = Book::new(10, 1, 8).unwrap();
+        let mut pos = Position::from_moves("44444433").unwrap();
+        book.put(&pos, 7);
+        let mut solver = Solver::new();
+        solver.set_book(Some(Arc::new(book)));
+        assert_eq!(solver.score(&mut pos).unwrap(), (7, 0));
+    }
+
+    #[test]
+    fn test_analysis_lines() {
+        let scores = [Some(-2), None, Some(0), Some(3), None, Some(-1), Some(12)];
+        let line = format!("2252576253462244111563365343671351441 {}", format_analysis(&scores));
+        assert_eq!(line, "2252576253462244111563365343671351441 -2 -1000 0 3 -1000 -1 12");
+        assert_eq!(parse_analysis(&line).unwrap(), (String::from("2252576253462244111563365343671351441"), scores));
+        assert_eq!(parse_analysis("0 0 0 0 0 0 0").unwrap().0, "");
+        assert!(parse_analysis("4453 1 2 3").is_err());
+        assert!(parse_analysis("4453 1 2 3 4 5 6 x").is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let input = "2252576253462244111563365343671351441\n4444444\n2252576253462244111563365343671351441\n";
+        let (mut output, mut errors) = (Vec::new(), Vec::new());
+        run(input.as_bytes(), &mut output, &mut errors, &mut Solver::new(), false, false).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("2252576253462244111563365343671351441 "));
+        assert_eq!(lines[0], lines[1]);
+        assert_eq!(String::from_utf8(errors).unwrap(), "Line 2: Invalid move 7 \"4444444\"\n");
+
+        let mut solver = Solver::new();
+        let strong = solve_line(&mut solver, "7422341735647741166133573473242566", false, false).unwrap();
+        let weak = solve_line(&mut solver, "7422341735647741166133573473242566", true, false).unwrap();
+        let score: i8 = strong.rsplit(' ').next().unwrap().parse().unwrap();
+        assert_eq!(weak, format!("7422341735647741166133573473242566 {}", score.signum()));
+
+        let analysis = solve_line(&mut solver, "7422341735647741166133573473242566", false, true).unwrap();
+        let (moves, scores) = parse_analysis(&analysis).unwrap();
+        assert_eq!(moves, "7422341735647741166133573473242566");
+        assert_eq!(scores.iter().flatten().max(), Some(&score));
+    }
+}
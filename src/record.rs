@@ -0,0 +1,457 @@
+// game records, for storing played games along with who played them and how they went. the format
+// is modelled on pgn from chess: headers as [Name "value"] lines, then the moves with their
+// numbers, ending with the result. a file can hold any number of games one after another
+//
+//   [First "Alice"]
+//   [Second "Bob"]
+//   [Date "2026.10.19"]
+//   [Result "1-0"]
+//   [TimeControl "300+5"]
+//   [Variant "standard"]
+//   [Board "7x6"]
+//
+//   1. 4 4 2. 5 {[%eval 18] threatens the bottom row} 5 3. 6 6 4. 7 1-0
+//
+// moves are columns 1-7, and like in pgn a full move is a stone for each player. a comment goes in
+// braces after the move it is about (and can not contain a closing brace), and [%eval <score>] in
+// a comment is the solver's score of the position after the move, for the first player. the result
+// is 1-0 when the first player won, 0-1 when the second player won, 1/2-1/2 for a draw and * for a
+// game that is not over. only 7x6 boards are supported, like everywhere else in the crate
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::iter::Peekable;
+use std::path::Path;
+use std::vec::IntoIter;
+use crate::{Position, Rules, Solver};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    FirstWins,
+    SecondWins,
+    Draw,
+    Unfinished,
+}
+
+impl GameResult {
+    fn parse(result: &str) -> Option<GameResult> {
+        match result {
+            "1-0" => Some(GameResult::FirstWins),
+            "0-1" => Some(GameResult::SecondWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unfinished),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            GameResult::FirstWins => "1-0",
+            GameResult::SecondWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unfinished => "*",
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Move {
+    pub col: usize, // 0-6
+    pub comment: Option<String>,
+    pub eval: Option<i8>, // score of the position after the move, for the first player
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameRecord {
+    pub first: Option<String>, // who played the first stone
+    pub second: Option<String>,
+    pub date: Option<String>, // yyyy.mm.dd like pgn, with ?? for the parts that are not known
+    pub time_control: Option<String>, // like pgn, 300+5 for 5 minutes each and 5 more seconds a move
+    pub result: GameResult,
+    pub rules: Rules,
+    pub other: Vec<(String, String)>, // any other headers, in the order they were read
+    pub moves: Vec<Move>,
+}
+
+impl Default for GameRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameRecord {
+
+    // an unfinished game of regular connect 4 with no moves yet
+    pub fn new() -> GameRecord {
+        GameRecord {
+            first: None,
+            second: None,
+            date: None,
+            time_control: None,
+            result: GameResult::Unfinished,
+            rules: Rules::Standard,
+            other: Vec::new(),
+            moves: Vec::new(),
+        }
+    }
+
+    // an unfinished game with the moves played to reach a position
+    pub fn from_position(pos: &Position) -> GameRecord {
+        let moves = pos.moves.iter().map(|&col| Move { col, ..Move::default() }).collect();
+        GameRecord { rules: pos.rules, moves, ..GameRecord::new() }
+    }
+
+    // the position after the moves. a move that wins the game can only be the last one and is not
+    // played, since a position can not be searched once the game is over. it is an error if the
+    // moves are not legal or the result does not match how the game ended
+    pub fn position(&self) -> Result<Position, String> {
+        let mut pos = Position::with_rules(self.rules);
+        for (i, mv) in self.moves.iter().enumerate() {
+            if mv.col >= 7 { return Err(format!("move {} is not a column: {}", i + 1, mv.col)) }
+            if !pos.is_legal_move(mv.col) { return Err(format!("move {} is in a full column", i + 1)) }
+            if pos.is_winning_move(mv.col) {
+                if i + 1 != self.moves.len() { return Err(format!("move {} ends the game, but there are moves after it", i + 1)) }
+                let winner = if pos.turn == 0 { GameResult::FirstWins } else { GameResult::SecondWins };
+                if self.result != winner { return Err(format!("move {} wins the game, but the result is {}", i + 1, self.result)) }
+                return Ok(pos)
+            }
+            pos.make_move(mv.col);
+        }
+        if pos.num_moves() == 42 && self.result != GameResult::Draw { return Err(format!("the board is full, but the result is {}", self.result)) }
+        Ok(pos)
+    }
+
+    // sets the evaluation of every move that does not have one yet to the solver's score after it,
+    // so annotating can carry on where an earlier run stopped. it is an error if the moves are not a
+    // valid game, see position
+    pub fn annotate(&mut self, solver: &mut Solver) -> Result<(), String> {
+        self.position()?;
+        let mut pos = Position::with_rules(self.rules);
+        for mv in &mut self.moves {
+            if mv.eval.is_some() {
+                pos.make_move(mv.col);
+                continue;
+            }
+            if pos.is_winning_move(mv.col) {
+                // the mover wins with this stone, so it scores like a win this turn
+                let score = ((43 - pos.num_moves()) / 2) as i8;
+                mv.eval = Some(if pos.turn == 0 { score } else { -score });
+                break;
+            }
+            pos.make_move(mv.col);
+            let (score, _) = solver.score(&mut pos.clone()).ok_or("the solver was stopped")?;
+            mv.eval = Some(if pos.turn == 0 { score } else { -score });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in [("First", &self.first), ("Second", &self.second), ("Date", &self.date)] {
+            if let Some(value) = value { writeln!(f, "[{name} \"{}\"]", escape(value))? }
+        }
+        writeln!(f, "[Result \"{}\"]", self.result)?;
+        if let Some(time_control) = &self.time_control { writeln!(f, "[TimeControl \"{}\"]", escape(time_control))? }
+        writeln!(f, "[Variant \"{}\"]", match self.rules { Rules::Standard => "standard", Rules::Cylinder => "cylinder" })?;
+        writeln!(f, "[Board \"7x6\"]")?;
+        for (name, value) in &self.other {
+            writeln!(f, "[{name} \"{}\"]", escape(value))?;
+        }
+        writeln!(f)?;
+
+        let mut words = Vec::new();
+        for (i, mv) in self.moves.iter().enumerate() {
+            if i % 2 == 0 { words.push(format!("{}.", i / 2 + 1)) }
+            words.push((mv.col + 1).to_string());
+            let eval = mv.eval.map(|eval| format!("[%eval {eval}]"));
+            // a closing brace would end the comment early, so it is left out
+            let comment = [eval, mv.comment.as_ref().map(|comment| comment.replace('}', ""))].into_iter().flatten().collect::<Vec<String>>();
+            if !comment.is_empty() { words.push(format!("{{{}}}", comment.join(" "))) }
+        }
+        words.push(self.result.to_string());
+
+        // the moves are wrapped at 80 characters, like pgn
+        let mut line = String::new();
+        for word in words {
+            if !line.is_empty() && line.len() + 1 + word.len() > 80 {
+                writeln!(f, "{line}")?;
+                line.clear();
+            }
+            if !line.is_empty() { line.push(' ') }
+            line.push_str(&word);
+        }
+        writeln!(f, "{line}")
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+enum Token {
+    Header(String, String),
+    Comment(String),
+    Word(String),
+}
+
+// reads every game in some text
+pub fn parse_games(text: &str) -> Result<Vec<GameRecord>, String> {
+    let mut tokens = tokenize(text)?.into_iter().peekable();
+    let mut games = Vec::new();
+    while tokens.peek().is_some() {
+        games.push(parse_game(&mut tokens).map_err(|e| format!("game {}: {e}", games.len() + 1))?);
+    }
+    Ok(games)
+}
+
+pub fn read_games<P: AsRef<Path>>(file_path: P) -> io::Result<Vec<GameRecord>> {
+    parse_games(&fs::read_to_string(file_path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// writes games with an empty line between them
+pub fn write_games<P: AsRef<Path>>(file_path: P, games: &[GameRecord]) -> io::Result<()> {
+    fs::write(file_path, games.iter().map(|game| game.to_string()).collect::<Vec<String>>().join("\n"))
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => (),
+            '[' => {
+                let header = header(&mut chars).ok_or_else(|| format!("line {line}: expected a header like [Name \"value\"]"))?;
+                tokens.push(header);
+            },
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => {
+                            if c == '\n' { line += 1 }
+                            comment.push(c);
+                        },
+                        None => return Err(format!("line {line}: comment is not closed")),
+                    }
+                }
+                tokens.push(Token::Comment(comment));
+            },
+            c => {
+                let mut word = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '{' || c == '[' { break }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            },
+        }
+    }
+    Ok(tokens)
+}
+
+// the rest of a header after its [
+fn header(chars: &mut Peekable<impl Iterator<Item = char>>) -> Option<Token> {
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+        name.push(c);
+    }
+    while chars.next_if(|c| *c == ' ').is_some() {}
+    if name.is_empty() || chars.next()? != '"' { return None }
+    let mut value = String::new();
+    loop {
+        match chars.next()? {
+            '"' => break,
+            '\\' => value.push(chars.next()?),
+            c => value.push(c),
+        }
+    }
+    while chars.next_if(|c| *c == ' ').is_some() {}
+    if chars.next()? != ']' { return None }
+    Some(Token::Header(name, value))
+}
+
+fn parse_game(tokens: &mut Peekable<IntoIter<Token>>) -> Result<GameRecord, String> {
+    let mut game = GameRecord::new();
+    let mut result = None;
+    while let Some(Token::Header(name, value)) = tokens.next_if(|token| matches!(token, Token::Header(..))) {
+        match name.as_str() {
+            "First" => game.first = Some(value),
+            "Second" => game.second = Some(value),
+            "Date" => game.date = Some(value),
+            "TimeControl" => game.time_control = Some(value),
+            "Result" => result = Some(GameResult::parse(&value).ok_or_else(|| format!("unknown result {value}"))?),
+            "Variant" => game.rules = match value.as_str() {
+                "standard" => Rules::Standard,
+                "cylinder" => Rules::Cylinder,
+                _ => return Err(format!("unknown variant {value}")),
+            },
+            "Board" => if value != "7x6" { return Err(format!("only 7x6 boards are supported, not {value}")) },
+            _ => game.other.push((name, value)),
+        }
+    }
+
+    loop {
+        match tokens.next() {
+            None => return Err(String::from("the moves do not end with a result")),
+            Some(Token::Header(name, _)) => return Err(format!("header {name} is after the moves")),
+            Some(Token::Comment(text)) => {
+                let Some(mv) = game.moves.last_mut() else { return Err(String::from("comment before the first move")) };
+                let (comment, eval) = parse_comment(&text)?;
+                if eval.is_some() { mv.eval = eval }
+                mv.comment = match (mv.comment.take(), comment) {
+                    (Some(before), Some(comment)) => Some(format!("{before} {comment}")),
+                    (before, comment) => before.or(comment),
+                };
+            },
+            Some(Token::Word(word)) => {
+                if let Some(end) = GameResult::parse(&word) {
+                    if result.is_some_and(|result| result != end) { return Err(format!("the moves end with {end}, but the result is {}", result.unwrap())) }
+                    game.result = end;
+                    return Ok(game)
+                }
+
+                // a move number like 12. or 12... can be on its own or in front of a move
+                let after_number = word.trim_start_matches(|c: char| c.is_ascii_digit());
+                let col = if after_number.len() < word.len() && after_number.starts_with('.') { after_number.trim_start_matches('.') } else { &word };
+                if col.is_empty() { continue }
+                match col.parse::<usize>() {
+                    Ok(col) if (1..=7).contains(&col) => game.moves.push(Move { col: col - 1, ..Move::default() }),
+                    _ => return Err(format!("move {} is not a column: '{word}'", game.moves.len() + 1)),
+                }
+            },
+        }
+    }
+}
+
+// splits a comment into its text and the evaluation in it, if any
+fn parse_comment(text: &str) -> Result<(Option<String>, Option<i8>), String> {
+    let mut eval = None;
+    let mut rest = text.to_string();
+    if let Some(start) = text.find("[%eval") {
+        let end = start + text[start..].find(']').ok_or_else(|| format!("evaluation is not closed: {text}"))?;
+        let value = text[start + 6..end].trim();
+        eval = Some(value.parse().map_err(|_| format!("evaluation is not a score: {value}"))?);
+        rest = format!("{}{}", &text[..start], &text[end + 1..]);
+    }
+    let rest = rest.trim();
+    Ok(((!rest.is_empty()).then(|| rest.to_string()), eval))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME: &str = "[First \"Alice\"]
+[Second \"Bob \\\"the builder\\\"\"]
+[Date \"2026.10.19\"]
+[Result \"1-0\"]
+[TimeControl \"300+5\"]
+[Variant \"standard\"]
+[Board \"7x6\"]
+[Event \"Club night\"]
+
+1. 4 4 2. 5 {[%eval 18] threatens the bottom row} 5 3. 6 6 4. 7 1-0
+";
+
+    #[test]
+    fn test_parse_and_write() {
+        let games = parse_games(&format!("{GAME}\n[Result \"*\"]\n\n1.4 {{center}} 4 2... 3 *\n")).unwrap();
+        assert_eq!(games.len(), 2);
+
+        let game = &games[0];
+        assert_eq!(game.first.as_deref(), Some("Alice"));
+        assert_eq!(game.second.as_deref(), Some("Bob \"the builder\""));
+        assert_eq!(game.time_control.as_deref(), Some("300+5"));
+        assert_eq!(game.result, GameResult::FirstWins);
+        assert_eq!(game.other, vec![(String::from("Event"), String::from("Club night"))]);
+        assert_eq!(game.moves.iter().map(|mv| mv.col + 1).collect::<Vec<usize>>(), vec![4, 4, 5, 5, 6, 6, 7]);
+        assert_eq!(game.moves[2], Move { col: 4, comment: Some(String::from("threatens the bottom row")), eval: Some(18) });
+        assert_eq!(game.to_string(), GAME);
+
+        assert_eq!(games[1].moves.iter().map(|mv| mv.col + 1).collect::<Vec<usize>>(), vec![4, 4, 3]);
+        assert_eq!(games[1].moves[0].comment.as_deref(), Some("center"));
+        assert_eq!(games[1].first, None);
+        assert_eq!(parse_games(&games[1].to_string()).unwrap()[0], games[1]);
+
+        // a closing brace can not be written inside a comment
+        let mut braces = games[1].clone();
+        braces.moves[0].comment = Some(String::from("center} 5 {x"));
+        assert_eq!(parse_games(&braces.to_string()).unwrap()[0].moves[0].comment.as_deref(), Some("center 5 {x"));
+    }
+
+    #[test]
+    fn test_rejects_bad_records() {
+        for (text, error) in [
+            ("[Result \"1-0\"]\n1. 4 4 0-1", "game 1: the moves end with 0-1, but the result is 1-0"),
+            ("1. 4 8 *", "game 1: move 2 is not a column: '8'"),
+            ("[Board \"8x7\"]\n1. 4 *", "game 1: only 7x6 boards are supported, not 8x7"),
+            ("1. 4 4", "game 1: the moves do not end with a result"),
+            ("{hello} 4 *", "game 1: comment before the first move"),
+            ("1. 4 {unclosed *", "line 1: comment is not closed"),
+            ("*\n[Result \"1-0 4", "line 2: expected a header like [Name \"value\"]"),
+        ] {
+            assert_eq!(parse_games(text).unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn test_position() {
+        let game = &parse_games(GAME).unwrap()[0];
+        let pos = game.position().unwrap();
+        assert_eq!(pos.moves, vec![3, 3, 4, 4, 5, 5]);
+
+        // the record of a position plays back to it
+        let mut record = GameRecord::from_position(&pos);
+        assert_eq!(record.position().unwrap().key(), pos.key());
+        assert_eq!(record.result, GameResult::Unfinished);
+
+        // the winning move has to match the result and be the last move
+        record.moves.push(Move { col: 6, ..Move::default() });
+        assert_eq!(record.position().err().as_deref(), Some("move 7 wins the game, but the result is *"));
+        record.moves.push(Move { col: 0, ..Move::default() });
+        assert_eq!(record.position().err().as_deref(), Some("move 7 ends the game, but there are moves after it"));
+
+        let mut cylinder = Position::with_rules(Rules::Cylinder);
+        cylinder.make_moves(vec![0, 0, 1]);
+        let record = parse_games(&GameRecord::from_position(&cylinder).to_string()).unwrap().remove(0);
+        assert_eq!(record.rules, Rules::Cylinder);
+        assert_eq!(record.position().unwrap().key(), cylinder.key());
+    }
+
+    #[test]
+    fn test_annotate() {
+        let mut record = GameRecord::from_position(&Position::from_moves("2252576253462244111563365343671351441").unwrap());
+        // the early positions take a while to solve, so they already have made up evaluations
+        for mv in &mut record.moves[..30] {
+            mv.eval = Some(0);
+        }
+        record.annotate(&mut Solver::new()).unwrap();
+        assert_eq!(record.moves[29].eval, Some(0));
+
+        // the position scores -1 for the second player, who is to play after an odd # of moves
+        assert_eq!(record.moves.last().unwrap().eval, Some(1));
+        assert!(record.moves.iter().all(|mv| mv.eval.is_some_and(|eval| eval.abs() <= 21)));
+
+        // a winning move scores like a win this turn
+        let mut game = parse_games(GAME).unwrap().remove(0);
+        for mv in &mut game.moves[..6] {
+            mv.eval = Some(0);
+        }
+        game.annotate(&mut Solver::new()).unwrap();
+        assert_eq!(game.moves[6].eval, Some(18));
+
+        // moves that already have an evaluation still have to be legal
+        game.moves.push(Move { col: 0, eval: Some(0), ..Move::default() });
+        assert_eq!(game.annotate(&mut Solver::new()).err().as_deref(), Some("move 7 ends the game, but there are moves after it"));
+        let mut full = GameRecord { moves: vec![Move { col: 0, eval: Some(0), ..Move::default() }; 7], ..GameRecord::default() };
+        assert_eq!(full.annotate(&mut Solver::new()).err().as_deref(), Some("move 7 is in a full column"));
+    }
+}
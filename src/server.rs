@@ -0,0 +1,207 @@
+// a small http server for solving positions from the web front-end, with json responses.
+// it only uses the standard library, and is meant to run locally next to the front-end.
+//
+// endpoints:
+//   GET /solve?moves=4453[&timeout=<ms>]
+//     200 {"moves":"4453","score":-3,"bestmove":3,"analysis":[-4,-3,-5,-3,-5,-9,-4]}
+//     analysis has a score per column (null if the column is full), columns and moves are numbered 1-7
+//     400 if the moves are not a valid game or the timeout is not a number of milliseconds it can
+//     wait for, 503 if solving takes longer than the timeout
+//   GET /health
+//     200 {"status":"ok"}
+//
+// every request shares one solver, so its transposition table keeps what earlier requests learned.
+// requests are solved one at a time, and time spent waiting for the solver counts towards the timeout
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::{Position, Solver};
+
+// used when a request does not give a timeout
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+
+// answers requests until the listener fails
+pub fn serve(listener: TcpListener, solver: Arc<Mutex<Solver>>) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else { continue };
+        let solver = Arc::clone(&solver);
+        thread::spawn(move || handle(stream, &solver));
+    }
+}
+
+fn handle(mut stream: TcpStream, solver: &Mutex<Solver>) {
+    let received = Instant::now();
+
+    // only the request line matters, but the headers are read so the client is not cut off
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() { return }
+    let mut header = String::new();
+    while reader.read_line(&mut header).is_ok_and(|n| n > 0) && header.trim() != "" {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => route(target, solver, received),
+        (Some(_), Some(_)) => (405, error_json("only GET is supported")),
+        _ => (400, error_json("malformed request")),
+    };
+
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Service Unavailable",
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+}
+
+// returns the status code and json body for a request target like /solve?moves=4453
+fn route(target: &str, solver: &Mutex<Solver>, received: Instant) -> (u16, String) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let param = |name: &str| query.split('&').find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='));
+
+    match path {
+        "/health" => (200, String::from("{\"status\":\"ok\"}")),
+        "/solve" => {
+            let moves = param("moves").unwrap_or("");
+            let timeout = match param("timeout").map(|ms| ms.parse()) {
+                None => DEFAULT_TIMEOUT_MS,
+                Some(Ok(ms)) => ms,
+                Some(Err(_)) => return (400, error_json("timeout must be a number of milliseconds")),
+            };
+            let mut pos = match Position::from_moves(moves) {
+                Ok(pos) => pos,
+                Err(e) => return (400, error_json(&e)),
+            };
+
+            let Some(deadline) = received.checked_add(Duration::from_millis(timeout)) else {
+                return (400, error_json("timeout is too long"))
+            };
+
+            let mut solver = solver.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            solver.set_deadline(Some(deadline));
+            let analysis = solver.analyze(&mut pos);
+            solver.set_deadline(None);
+
+            match analysis {
+                Some(scores) => (200, solve_json(moves, &scores)),
+                None => (503, error_json("search timed out")),
+            }
+        },
+        _ => (404, error_json("not found")),
+    }
+}
+
+fn solve_json(moves: &str, scores: &[Option<i8>; 7]) -> String {
+    // the center most of the best columns
+    let best = [3, 2, 4, 1, 5, 0, 6].into_iter().filter(|&col| scores[col].is_some()).max_by_key(|&col| scores[col]);
+    let analysis: Vec<String> = scores.iter().map(|s| match s {
+        Some(s) => s.to_string(),
+        None => String::from("null"),
+    }).collect();
+
+    match best {
+        Some(col) => format!(
+            "{{\"moves\":{},\"score\":{},\"bestmove\":{},\"analysis\":[{}]}}",
+            json_string(moves), scores[col].unwrap(), col + 1, analysis.join(",")
+        ),
+        // a full board is a tie with nothing left to play
+        None => format!("{{\"moves\":{},\"score\":0,\"bestmove\":null,\"analysis\":[{}]}}", json_string(moves), analysis.join(",")),
+    }
+}
+
+fn error_json(message: &str) -> String {
+    format!("{{\"error\":{}}}", json_string(message))
+}
+
+// quotes a string for json
+fn json_string(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::SocketAddr;
+
+    fn start_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let solver = Arc::new(Mutex::new(Solver::new()));
+        thread::spawn(move || serve(listener, solver));
+        addr
+    }
+
+    // sends a request over loopback, returns the status line and the body
+    fn get(addr: SocketAddr, target: &str) -> (String, String) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {target} HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), body.to_string())
+    }
+
+    #[test]
+    fn test_solve() {
+        let addr = start_server();
+        let (status, body) = get(addr, "/solve?moves=2252576253462244111563365343671351441");
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(body, "{\"moves\":\"2252576253462244111563365343671351441\",\"score\":-1,\"bestmove\":6,\"analysis\":[null,null,null,null,null,-1,-2]}");
+    }
+
+    #[test]
+    fn test_concurrent_requests() {
+        let addr = start_server();
+        let clients: Vec<_> = ["7422341735647741166133573473242566", "23163416124767223154467471272416755633"]
+            .into_iter()
+            .map(|moves| thread::spawn(move || get(addr, &format!("/solve?moves={moves}"))))
+            .collect();
+        for client in clients {
+            assert_eq!(client.join().unwrap().0, "HTTP/1.1 200 OK");
+        }
+    }
+
+    #[test]
+    fn test_timeout() {
+        let addr = start_server();
+        let (status, body) = get(addr, "/solve?moves=4&timeout=20");
+        assert_eq!(status, "HTTP/1.1 503 Service Unavailable");
+        assert_eq!(body, "{\"error\":\"search timed out\"}");
+    }
+
+    #[test]
+    fn test_bad_requests() {
+        let addr = start_server();
+        assert_eq!(get(addr, "/solve?moves=4444444").0, "HTTP/1.1 400 Bad Request");
+        assert_eq!(get(addr, "/solve?moves=4&timeout=soon").0, "HTTP/1.1 400 Bad Request");
+        assert_eq!(get(addr, "/elsewhere").0, "HTTP/1.1 404 Not Found");
+        assert_eq!(get(addr, "/health").1, "{\"status\":\"ok\"}");
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("say \"hi\"\n"), "\"say \\\"hi\\\"\\u000a\"");
+    }
+}
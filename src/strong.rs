@@ -0,0 +1,254 @@
+// a strong solution of the opening: the exact score of every position reachable in the first few
+// moves, solved by a batch job that can be stopped and picked up again.
+//
+// a job lives in a directory. job.txt records the moves of the position it starts from (none for
+// the empty board), how many plies after it are covered and how many positions go in each shard.
+// the positions of each ply are sorted by Position::hash and cut into shards, each written as a
+// database file (see src/database.rs) named like ply-08-00012.db. a shard is written under a
+// temporary name and renamed once it is complete, and the solver's transposition table is saved to
+// table.c4tt after every shard, so a job that is restarted skips the finished shards and carries on
+// with a warm table. mirrored positions share a hash, so only one of them is stored
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::database::{Database, DatabaseBuilder};
+use crate::{Position, Solver};
+
+pub struct Job {
+    pub dir: PathBuf,
+    pub root: String, // moves of the starting position, numbered 1-7
+    pub plies: usize, // every position up to this many moves after the root is solved
+    pub shard_size: usize, // positions per shard
+}
+
+impl Job {
+
+    // starts a job in a directory, or checks that the job already there is the same one
+    pub fn create<P: AsRef<Path>>(dir: P, root: &str, plies: usize, shard_size: usize) -> io::Result<Job> {
+        let dir = dir.as_ref().to_path_buf();
+        Position::from_moves(root).map_err(|e| invalid(format!("invalid root position {root}: {e}")))?;
+        // the moves of a position are packed 3 bits each into a u64
+        if plies > 21 || shard_size == 0 { return Err(invalid(String::from("a job covers at most 21 plies, in shards of at least 1 position"))) }
+        let job = Job { dir, root: root.to_string(), plies, shard_size };
+        fs::create_dir_all(&job.dir)?;
+        match Job::open(&job.dir) {
+            Ok(existing) if (&existing.root, existing.plies, existing.shard_size) == (&job.root, plies, shard_size) => Ok(existing),
+            Ok(existing) => Err(invalid(format!("{} already holds a job of {} plies from \"{}\" with shards of {}", job.dir.display(), existing.plies, existing.root, existing.shard_size))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                fs::write(job.dir.join("job.txt"), format!("root {root}\nplies {plies}\nshard_size {shard_size}\n"))?;
+                Ok(job)
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    // an existing job
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<Job> {
+        let dir = dir.as_ref().to_path_buf();
+        let manifest = fs::read_to_string(dir.join("job.txt"))?;
+        let mut fields: HashMap<&str, &str> = HashMap::new();
+        for line in manifest.lines() {
+            let (name, value) = line.split_once(' ').unwrap_or((line, ""));
+            fields.insert(name, value.trim());
+        }
+        let number = |name| fields.get(name).and_then(|value| value.parse().ok());
+        match (fields.get("root"), number("plies"), number("shard_size")) {
+            (Some(root), Some(plies), Some(shard_size)) => Ok(Job { dir, root: root.to_string(), plies, shard_size }),
+            _ => Err(invalid(String::from("job.txt should have root, plies and shard_size"))),
+        }
+    }
+
+    pub fn shard_path(&self, ply: usize, shard: usize) -> PathBuf {
+        self.dir.join(format!("ply-{ply:02}-{shard:05}.db"))
+    }
+
+    fn table_path(&self) -> PathBuf {
+        self.dir.join("table.c4tt")
+    }
+
+    // every finished shard
+    pub fn shards(&self) -> Vec<PathBuf> {
+        let mut shards = Vec::new();
+        for ply in 0..=self.plies {
+            let mut shard = 0;
+            while self.shard_path(ply, shard).exists() {
+                shards.push(self.shard_path(ply, shard));
+                shard += 1;
+            }
+        }
+        shards
+    }
+
+    pub fn root_position(&self) -> Position {
+        Position::from_moves(&self.root).unwrap()
+    }
+
+    // solves every shard that is not finished yet, deepest ply first so the table helps the
+    // shallower plies. progress is called after each shard with the ply, shard and # of shards in
+    // the ply, and stops the job when it returns false
+    pub fn run(&self, mut progress: impl FnMut(usize, usize, usize) -> bool) -> io::Result<()> {
+        let mut solver = Solver::new();
+        if self.table_path().exists() { solver.load_table(self.table_path())? }
+
+        let root = self.root_position();
+        for ply in (0..=self.plies).rev() {
+            let positions = positions_at(&root, ply);
+            let shards = positions.len().div_ceil(self.shard_size);
+            for (shard, chunk) in positions.chunks(self.shard_size).enumerate() {
+                let path = self.shard_path(ply, shard);
+                if path.exists() { continue }
+
+                let mut builder = DatabaseBuilder::new();
+                for &(_, packed) in chunk {
+                    let mut pos = unpack(&root, packed, ply);
+                    let (score, _) = solver.score(&mut pos).unwrap();
+                    builder.add(&pos, score);
+                }
+                builder.write(&path)?;
+                solver.save_table(self.table_path())?;
+
+                if !progress(ply, shard, shards) { return Ok(()) }
+            }
+        }
+        Ok(())
+    }
+
+    // checks that a shard holds exactly the positions it should, and solves every nth of them again
+    // with a fresh solver. returns a description of everything that was wrong
+    pub fn verify_shard(&self, ply: usize, shard: usize, every: usize) -> io::Result<Vec<String>> {
+        let root = self.root_position();
+        let positions = positions_at(&root, ply);
+        let Some(chunk) = positions.chunks(self.shard_size).nth(shard) else {
+            return Ok(vec![format!("ply {ply} has no shard {shard}")])
+        };
+        let database = Database::open(self.shard_path(ply, shard))?;
+
+        let mut problems = Vec::new();
+        if database.len() != chunk.len() {
+            problems.push(format!("ply {ply} shard {shard} holds {} positions, expected {}", database.len(), chunk.len()));
+        }
+        let mut solver = Solver::new();
+        for (i, &(_, packed)) in chunk.iter().enumerate() {
+            let mut pos = unpack(&root, packed, ply);
+            let moves = self.root.clone() + &moves_string(packed, ply);
+            match database.get(&pos) {
+                None => problems.push(format!("ply {ply} shard {shard}: {moves} is missing")),
+                Some(stored) if i % every.max(1) == 0 => {
+                    let (score, _) = solver.score(&mut pos).unwrap();
+                    if score != stored { problems.push(format!("ply {ply} shard {shard}: {moves} is stored as {stored} but solves as {score}")) }
+                },
+                Some(_) => (),
+            }
+        }
+        Ok(problems)
+    }
+}
+
+// every position a number of moves after the root where nobody has won, one per pair of mirrored
+// positions, as its hash and the moves after the root packed 3 bits each (see unpack), sorted by hash
+pub fn positions_at(root: &Position, ply: usize) -> Vec<(u64, u64)> {
+    let mut frontier: Vec<(u64, u64)> = vec![(root.hash(), 0)];
+    for depth in 0..ply {
+        let mut next: HashMap<u64, u64> = HashMap::new();
+        for &(_, packed) in &frontier {
+            let pos = unpack(root, packed, depth);
+            for col in 0..7 {
+                if !pos.is_legal_move(col) || pos.is_winning_move(col) { continue }
+                let mut child = pos.clone();
+                child.make_move(col);
+                next.entry(child.hash()).or_insert(packed | (col as u64) << (3 * depth));
+            }
+        }
+        frontier = next.into_iter().collect();
+    }
+    frontier.sort_unstable();
+    frontier
+}
+
+// replays packed moves from the root, the first move in the lowest 3 bits
+pub fn unpack(root: &Position, packed: u64, ply: usize) -> Position {
+    let mut pos = root.clone();
+    for i in 0..ply {
+        pos.make_move((packed >> (3 * i) & 0b111) as usize);
+    }
+    pos
+}
+
+// packed moves numbered 1-7 like in the test files
+pub fn moves_string(packed: u64, ply: usize) -> String {
+    (0..ply).map(|i| char::from_digit((packed >> (3 * i) & 0b111) as u32 + 1, 10).unwrap()).collect()
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::perft::perft;
+
+    // late enough in a game that every position solves quickly
+    const ROOT: &str = "2252576253462244111563365343";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("c4strong-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn test_positions_at() {
+        // perft counts won positions too, which are never played on
+        let start = Position::new();
+        let counts = perft(&start, 8);
+        for count in &counts[..=6] {
+            assert_eq!(positions_at(&start, count.ply).len() as u64, count.folded);
+        }
+        let positions = positions_at(&start, 8);
+        assert!((positions.len() as u64) < counts[8].folded);
+        assert!(positions.windows(2).all(|w| w[0].0 < w[1].0));
+        for &(hash, packed) in positions.iter().step_by(97) {
+            assert_eq!(unpack(&start, packed, 8).hash(), hash);
+            assert_eq!(Position::from_moves(&moves_string(packed, 8)).unwrap().hash(), hash);
+        }
+    }
+
+    #[test]
+    fn test_resumes_and_verifies() {
+        let dir = temp_dir("job");
+        let _ = fs::remove_dir_all(&dir);
+        let root = Position::from_moves(ROOT).unwrap();
+        let shards = |ply| positions_at(&root, ply).len().div_ceil(4);
+
+        // stop after the first shard, then pick up where it left off
+        let job = Job::create(&dir, ROOT, 2, 4).unwrap();
+        let mut finished = Vec::new();
+        job.run(|ply, shard, _| { finished.push((ply, shard)); false }).unwrap();
+        assert_eq!(finished, [(2, 0)]);
+        assert!(Job::create(&dir, ROOT, 3, 4).is_err());
+
+        let job = Job::create(&dir, ROOT, 2, 4).unwrap();
+        job.run(|ply, shard, _| { finished.push((ply, shard)); true }).unwrap();
+        assert_eq!(finished.len(), shards(2) + shards(1) + shards(0));
+        assert_eq!(finished[shards(2)], (1, 0));
+        assert_eq!(job.shards().len(), finished.len());
+
+        let opened = Job::open(&dir).unwrap();
+        assert_eq!(opened.root, ROOT);
+        for (ply, shard) in [(0, 0), (1, 0), (2, shards(2) - 1)] {
+            assert!(opened.verify_shard(ply, shard, 1).unwrap().is_empty());
+        }
+
+        // a shard with a wrong score is caught
+        let mut builder = DatabaseBuilder::new();
+        for &(_, packed) in &positions_at(&root, 2)[..4] {
+            builder.add(&unpack(&root, packed, 2), 20);
+        }
+        builder.write(job.shard_path(2, 0)).unwrap();
+        let problems = opened.verify_shard(2, 0, 1).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(problems.len(), 4);
+        assert!(problems[0].contains("is stored as 20"));
+    }
+}
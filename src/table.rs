@@ -0,0 +1,106 @@
+// saving the transposition table made by create_tt to disk, so a long analysis can be picked up
+// again, or a server can start with a table that already knows the hard positions.
+//
+// a table file is a header, the raw entries and a checksum, all little endian:
+//   bytes 0-3     "C4TT"
+//   bytes 4-7     format version, bumped whenever the entry layout in create_tt changes
+//   bytes 8-15    # of entries
+//   then 8 bytes per entry, followed by the fnv-1a hash of everything before it
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"C4TT";
+pub const VERSION: u32 = 3;
+const HEADER_LEN: usize = 16;
+
+pub fn to_bytes(tt: &[u64]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + 8 * tt.len() + 8);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(tt.len() as u64).to_le_bytes());
+    for entry in tt {
+        bytes.extend_from_slice(&entry.to_le_bytes());
+    }
+    bytes.extend_from_slice(&checksum(&bytes).to_le_bytes());
+    bytes
+}
+
+// reads a table written by to_bytes, rejecting it if it was made for a different table or got corrupted
+pub fn from_bytes(bytes: &[u8]) -> Result<Box<[u64; 1000003]>, String> {
+    if bytes.len() < HEADER_LEN + 8 || &bytes[..4] != MAGIC { return Err(String::from("not a transposition table file")) }
+    let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    if version != VERSION { return Err(format!("table is format version {version}, expected {VERSION}")) }
+    let entries = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+    if entries != 1000003 { return Err(format!("table has {entries} entries, expected 1000003")) }
+    if bytes.len() != HEADER_LEN + 8 * 1000003 + 8 { return Err(String::from("table file is truncated")) }
+
+    let (body, stored) = bytes.split_at(bytes.len() - 8);
+    if checksum(body).to_le_bytes() != stored { return Err(String::from("table file is corrupted, the checksum does not match")) }
+
+    let tt: Vec<u64> = body[HEADER_LEN..].chunks_exact(8).map(|entry| u64::from_le_bytes(entry.try_into().unwrap())).collect();
+    Ok(tt.into_boxed_slice().try_into().unwrap())
+}
+
+pub fn save<P: AsRef<Path>>(tt: &[u64], file_path: P) -> io::Result<()> {
+    fs::write(file_path, to_bytes(tt))
+}
+
+pub fn load<P: AsRef<Path>>(file_path: P) -> io::Result<Box<[u64; 1000003]>> {
+    from_bytes(&fs::read(file_path)?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+// 64 bit fnv-1a
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Position, Solver};
+
+    #[test]
+    fn test_round_trip() {
+        let mut tt = crate::create_tt();
+        tt[0] = 1;
+        tt[12345] = u64::MAX;
+        let bytes = to_bytes(&tt[..]);
+        assert_eq!(bytes.len(), 16 + 8 * 1000003 + 8);
+        assert_eq!(from_bytes(&bytes).unwrap(), tt);
+    }
+
+    #[test]
+    fn test_rejects_bad_files() {
+        let bytes = to_bytes(&crate::create_tt()[..]);
+
+        let mut corrupted = bytes.clone();
+        corrupted[HEADER_LEN + 100] ^= 1;
+        assert!(from_bytes(&corrupted).unwrap_err().contains("checksum"));
+
+        let mut newer = bytes.clone();
+        newer[4] = 9;
+        assert!(from_bytes(&newer).unwrap_err().contains("version 9"));
+
+        assert!(from_bytes(&to_bytes(&[0; 10])).unwrap_err().contains("10 entries"));
+        assert!(from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(from_bytes(b"not a table at all").is_err());
+    }
+
+    #[test]
+    fn test_loaded_table_is_reused() {
+        let path = std::env::temp_dir().join(format!("c4tt-test-{}", std::process::id()));
+        let mut pos = Position::from_moves("2252576253462244111563365343671351441").unwrap();
+        let mut solver = Solver::new();
+        let (score, searched) = solver.score(&mut pos).unwrap();
+        solver.save_table(&path).unwrap();
+
+        let mut warm = Solver::new();
+        warm.load_table(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let (warm_score, warm_searched) = warm.score(&mut pos).unwrap();
+        assert_eq!(warm_score, score);
+        assert!(warm_searched < searched);
+    }
+}
@@ -0,0 +1,143 @@
+// an endgame tablebase: the exact score of every position with at least some number of stones,
+// worked out backwards from the end of the game instead of searched. a position is scored once
+// every position after it is, so the layers are scored from 42 stones down: a position with a
+// winning move scores a win, a full board is a draw, and otherwise the score is the best of minus
+// the score of each position a move leads to.
+//
+// tablebases are written as databases (see src/database.rs), whose fewest moves is the number of
+// stones, and the solver probes one set with Solver::set_tablebase before searching a position.
+// every position with 34 or more stones after the empty board is far too many to hold in memory,
+// so a tablebase covers one subtree: the positions that follow some root position. every layer after
+// the root is held while generating, so the root has to be late in the game
+
+use std::collections::HashMap;
+use crate::database::{Database, DatabaseBuilder};
+use crate::{Position, Solver};
+
+// most empty spots a root can have. 24 is around 10 million positions, and each one more spot is a
+// few times as many
+pub const MAX_EMPTY: usize = 24;
+
+// every position reachable from the root where nobody has won, one per pair of mirrored positions,
+// as a layer for each number of stones from min_stones up to 42
+pub fn layers(root: &Position, min_stones: usize) -> Vec<(usize, Vec<Position>)> {
+    let mut layers = Vec::new();
+    let mut frontier: Vec<Position> = vec![root.clone()];
+    for stones in root.num_moves()..=42 {
+        let mut next: HashMap<u64, Position> = HashMap::new();
+        for pos in &frontier {
+            for col in 0..7 {
+                if !pos.is_legal_move(col) || pos.is_winning_move(col) { continue }
+                let mut child = pos.clone();
+                child.make_move(col);
+                next.entry(child.hash()).or_insert(child);
+            }
+        }
+        let layer = std::mem::replace(&mut frontier, next.into_values().collect());
+        if stones >= min_stones { layers.push((stones, layer)) }
+    }
+    layers
+}
+
+// scores every position with at least min_stones stones after the root by retrograde analysis
+pub fn generate(root: &Position, min_stones: usize) -> DatabaseBuilder {
+    let mut builder = DatabaseBuilder::new();
+    let mut later: HashMap<u64, i8> = HashMap::new(); // scores of the layer with one more stone
+
+    for (stones, layer) in layers(root, min_stones).into_iter().rev() {
+        let mut scores: HashMap<u64, i8> = HashMap::with_capacity(layer.len());
+        for pos in layer {
+            let score = if (0..7).any(|col| pos.is_legal_move(col) && pos.is_winning_move(col)) {
+                ((43 - stones) / 2) as i8
+            } else if stones == 42 {
+                0
+            } else {
+                // every move leads to a position in the later layer, since none of them win
+                (0..7).filter(|&col| pos.is_legal_move(col)).map(|col| {
+                    let mut child = pos.clone();
+                    child.make_move(col);
+                    -later[&child.hash()]
+                }).max().unwrap()
+            };
+            scores.insert(pos.hash(), score);
+            builder.add(&pos, score);
+        }
+        later = scores;
+    }
+    builder
+}
+
+// solves every nth position of the tablebase again with a regular search and compares the scores.
+// returns a description of everything that was wrong
+pub fn verify(tablebase: &Database, root: &Position, every: usize) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut solver = Solver::new();
+    let mut i = 0;
+    for (_, layer) in layers(root, tablebase.min_moves()) {
+        for mut pos in layer {
+            let stored = tablebase.get(&pos);
+            if stored.is_none() || i % every.max(1) == 0 {
+                let moves: String = pos.moves.iter().map(|&col| char::from_digit(col as u32 + 1, 10).unwrap()).collect();
+                let (score, _) = solver.score(&mut pos).unwrap();
+                match stored {
+                    None => problems.push(format!("{moves} is missing")),
+                    Some(stored) if stored != score => problems.push(format!("{moves} is stored as {stored} but solves as {score}")),
+                    Some(_) => (),
+                }
+            }
+            i += 1;
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::Arc;
+
+    // late enough in a game that there are not many positions after it
+    const ROOT: &str = "2252576253462244111563365343";
+
+    #[test]
+    fn test_matches_forward_search() {
+        let root = Position::from_moves(ROOT).unwrap();
+        let builder = generate(&root, 34);
+        let path = std::env::temp_dir().join(format!("c4tb-test-{}", std::process::id()));
+        builder.write(&path).unwrap();
+        let tablebase = Database::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!((tablebase.min_moves(), tablebase.max_moves()), (34, 42));
+        assert_eq!(tablebase.len(), layers(&root, 34).iter().map(|(_, layer)| layer.len()).sum::<usize>());
+        assert!(verify(&tablebase, &root, 7).is_empty());
+
+        // the solver gets the same scores while probing the tablebase
+        let mut solver = Solver::new();
+        solver.set_tablebase(Some(Arc::new(tablebase)));
+        for moves in ["", "4", "47", "71"] {
+            let mut pos = Position::from_moves(&(ROOT.to_string() + moves)).unwrap();
+            let expected = Solver::new().score(&mut pos).unwrap().0;
+            assert_eq!(solver.score(&mut pos).unwrap().0, expected);
+        }
+    }
+
+    #[test]
+    fn test_verify_catches_wrong_scores() {
+        let root = Position::from_moves(ROOT).unwrap();
+        let mut builder = DatabaseBuilder::new();
+        for (_, layer) in layers(&root, 40) {
+            for pos in layer {
+                builder.add(&pos, 9);
+            }
+        }
+        let path = std::env::temp_dir().join(format!("c4tb-wrong-{}", std::process::id()));
+        builder.write(&path).unwrap();
+        let tablebase = Database::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let problems = verify(&tablebase, &root, 1);
+        assert_eq!(problems.len(), tablebase.len());
+        assert!(problems[0].contains("is stored as 9"));
+    }
+}
@@ -0,0 +1,105 @@
+// checking the solver against the solved test files, so a wrong score fails loudly instead of
+// being one "INCORRECT" line among thousands. a sample runs in cargo test, and the verify binary
+// checks whole files offline
+
+use std::thread;
+use crate::bench::TestCase;
+use crate::{Position, Solver};
+
+// a position the solver got wrong
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    pub file: String,
+    pub line: usize, // counting from 1
+    pub moves: String,
+    pub expected: i8,
+    pub predicted: i8,
+}
+
+// solves every case, given with its line number, split across the given number of threads, and
+// returns the ones that were wrong. each thread keeps one solver for all its cases, so a table entry
+// that does not belong to the position being solved shows up here too
+pub fn verify(file: &str, cases: &[(usize, &TestCase)], threads: usize) -> Vec<Mismatch> {
+    let chunk_size = cases.len().div_ceil(threads.max(1)).max(1);
+    let mut mismatches: Vec<Mismatch> = thread::scope(|s| {
+        let workers: Vec<_> = cases.chunks(chunk_size).map(|cases| {
+            s.spawn(move || {
+                let mut solver = Solver::new();
+                let mut found = Vec::new();
+                for &(line, case) in cases {
+                    let mut pos = match Position::from_moves(&case.moves) {
+                        Ok(pos) => pos,
+                        Err(e) => panic!("{file} line {line}: invalid test position {}: {e}", case.moves),
+                    };
+                    let (predicted, _) = solver.score(&mut pos).unwrap();
+                    if predicted != case.score {
+                        found.push(Mismatch { file: file.to_string(), line, moves: case.moves.clone(), expected: case.score, predicted });
+                    }
+                }
+                found
+            })
+        }).collect();
+        workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+    });
+    mismatches.sort_by_key(|m| m.line);
+    mismatches
+}
+
+// every nth case, starting with the first, with the line numbers they had in the file
+pub fn sample(cases: &[TestCase], every: usize) -> Vec<(usize, &TestCase)> {
+    cases.iter().enumerate().step_by(every.max(1)).map(|(i, case)| (i + 1, case)).collect()
+}
+
+// ok if nothing was wrong, otherwise an error listing every mismatch
+pub fn summary(checked: usize, mismatches: &[Mismatch]) -> Result<(), String> {
+    if mismatches.is_empty() { return Ok(()) }
+    let mut report = format!("{} of {} positions solved incorrectly:\n", mismatches.len(), checked);
+    for m in mismatches {
+        report += &format!("  {} line {}: {} expected {} but got {}\n", m.file, m.line, m.moves, m.expected, m.predicted);
+    }
+    Err(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::read_test_file;
+
+    // a sample of every file that solves quickly in a debug build, the verify binary covers the rest
+    #[test]
+    fn test_sample_of_test_files() {
+        let mut checked = 0;
+        let mut mismatches = Vec::new();
+        for (file, every) in [("End-Easy", 10), ("Middle-Easy", 20), ("Middle-Medium", 200), ("Start-Easy", 50)] {
+            let cases = read_test_file(format!("test_files/{file}.txt")).unwrap();
+            let cases = sample(&cases, every);
+            mismatches.extend(verify(file, &cases, 4));
+            checked += cases.len();
+        }
+        if let Err(report) = summary(checked, &mismatches) { panic!("{report}") }
+    }
+
+    #[test]
+    fn test_parallel_matches_serial() {
+        let mut cases = read_test_file("test_files/End-Easy.txt").unwrap();
+        cases.truncate(40);
+        assert!(verify("End-Easy", &sample(&cases, 1), 4).is_empty());
+
+        // a wrong expected score is reported with the line it came from
+        cases[25].score += 1;
+        let serial = verify("End-Easy", &sample(&cases, 1), 1);
+        assert_eq!(verify("End-Easy", &sample(&cases, 1), 4), serial);
+        assert_eq!(serial.len(), 1);
+        assert_eq!(serial[0].line, 26);
+        assert_eq!(serial[0].predicted, serial[0].expected - 1);
+    }
+
+    #[test]
+    fn test_summary() {
+        assert!(summary(5, &[]).is_ok());
+        let m = Mismatch { file: String::from("End-Easy"), line: 3, moves: String::from("4453"), expected: 1, predicted: -1 };
+        let report = summary(5, &[m]).unwrap_err();
+        assert!(report.starts_with("1 of 5 positions solved incorrectly"));
+        assert!(report.contains("End-Easy line 3: 4453 expected 1 but got -1"));
+    }
+}
@@ -0,0 +1,532 @@
+// rules from Victor Allis' program VICTOR (A Knowledge-based Approach of Connect-Four, 1988), which
+// prove that the second player can at least draw, or even win, without searching.
+//
+// the second player's strategy is made of rules that between them cover every empty spot, without
+// sharing any. whenever the first player plays a spot, the second player answers with a spot of the
+// same rule, so the second player never has to move first and the first player has to be the one
+// to play. a rule decides which spots each player can end up with, and a group (four spots in a
+// row) of the first player is refuted when some rule makes sure the first player can not get every
+// spot of it. the rules, where a spot is directly playable when everything below it is filled:
+//   claimeven     two empty spots above each other, the upper one on an even row. the second
+//                 player answers the lower spot with the upper one, so gets the upper spot
+//   vertical      the same with the upper spot on an odd row, which happens above the other rules
+//   baseinverse   two directly playable spots, the second player answers one with the other
+//   baseclaim     three directly playable spots a, b and c and the spot above b. the second player
+//                 answers a and b with c and c with b, then the last two spots with each other
+//   lowinverse    two columns with two empty spots each, the lower ones directly playable. the
+//                 second player answers a spot with the spot at the same height in the other column
+//   highinverse   two columns with three empty spots each, the lowest ones directly playable. the
+//                 second player answers the lowest spot of a column with the one above it, the
+//                 middle one with the top one, and the top one with the top spot of the other
+//                 column, or its lowest spot if the other column was not played yet
+//   aftereven     a group of the second player made only of spots it gets from the rules. the
+//                 second player completes it before the first player can fill a spot above it in
+//                 every one of its columns, so groups of the first player that need to are refuted
+//   before        a group of the second player that is not on the top row, and a group of the first
+//                 player with the spot directly above every empty spot of it. if no rule lets the
+//                 first player get its spots of the first player's group along with a spot of the
+//                 before group, like a vertical that gives the second player one of a spot and the
+//                 one above it, then the second player has completed the before group by the time
+//                 the first player could complete theirs, so it is refuted
+//   specialbefore a before that only works because of a baseinverse: with its two spots unpaired,
+//                 the first player could take one in the before group along with the other, which
+//                 is in the first player's group
+// the spots each player can end up with are found by playing out every order of moves within a
+// rule, so the rules only need to say how the second player answers.
+//
+// the rules above the lowest spots of each column are always claimevens and verticals, so a proof
+// is a choice of what covers the lowest spots of each column, and every choice is tried.
+
+use std::collections::HashMap;
+use crate::{Position, Rules};
+
+// one bit for every spot in the given row, counting from 0 at the bottom
+const ROW: u64 = 4432676798593;
+
+// every group of four spots in a row on the board
+const GROUPS: [u64; 69] = groups();
+
+const fn groups() -> [u64; 69] {
+    let mut groups = [0; 69];
+    let mut n = 0;
+    let mut col = 0;
+    while col < 7 {
+        let mut row = 0;
+        while row < 6 {
+            let s: u64 = 1 << (7 * col + row);
+            if row <= 2 { groups[n] = s | s << 1 | s << 2 | s << 3; n += 1 }
+            if col <= 3 { groups[n] = s | s << 7 | s << 14 | s << 21; n += 1 }
+            if col <= 3 && row <= 2 { groups[n] = s | s << 8 | s << 16 | s << 24; n += 1 }
+            if col <= 3 && row >= 3 { groups[n] = s | s << 6 | s << 12 | s << 18; n += 1 }
+            row += 1;
+        }
+        col += 1;
+    }
+    groups
+}
+
+// the groups each spot is in, as bits of GROUPS
+const SPOT_GROUPS: [u128; 49] = spot_groups();
+
+const fn spot_groups() -> [u128; 49] {
+    let mut spot_groups = [0; 49];
+    let mut i = 0;
+    while i < 69 {
+        let mut bit = 0;
+        while bit < 49 {
+            if GROUPS[i] & 1 << bit != 0 { spot_groups[bit] |= 1 << i }
+            bit += 1;
+        }
+        i += 1;
+    }
+    spot_groups
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Rule {
+    Claimeven(u64, u64), // lower and upper spot
+    Vertical(u64, u64), // lower and upper spot
+    Baseinverse(u64, u64),
+    Baseclaim(u64, u64, u64, u64), // a, b, c and the spot above b
+    Lowinverse([u64; 2], [u64; 2]), // the spots of each column, from the bottom
+    Highinverse([u64; 3], [u64; 3]), // the spots of each column, from the bottom
+}
+
+impl Rule {
+    pub fn spots(&self) -> u64 {
+        match *self {
+            Rule::Claimeven(a, b) | Rule::Vertical(a, b) | Rule::Baseinverse(a, b) => a | b,
+            Rule::Baseclaim(a, b, c, d) => a | b | c | d,
+            Rule::Lowinverse(x, y) => x[0] | x[1] | y[0] | y[1],
+            Rule::Highinverse(x, y) => x[0] | x[1] | x[2] | y[0] | y[1] | y[2],
+        }
+    }
+
+    // the second player's answer when the first player plays a spot of this rule, and filled
+    // spots of the rule were already played
+    fn answer(&self, filled: u64, spot: u64) -> u64 {
+        let other = |a: u64, b: u64| if spot == a { b } else { a };
+        match *self {
+            Rule::Claimeven(_, upper) | Rule::Vertical(_, upper) => upper,
+            Rule::Baseinverse(a, b) => other(a, b),
+            Rule::Baseclaim(_, b, c, _) if filled == 0 => if spot == c { b } else { c },
+            // the last two spots answer each other
+            Rule::Baseclaim(..) => self.spots() & !filled & !spot,
+            Rule::Lowinverse(x, y) => {
+                let level = if spot & (x[0] | y[0]) != 0 { 0 } else { 1 };
+                other(x[level], y[level])
+            },
+            Rule::Highinverse(x, y) => {
+                let (col, other) = if spot & (x[0] | x[1] | x[2]) != 0 { (x, y) } else { (y, x) };
+                if spot == col[0] { col[1] } else if spot == col[1] { col[2] } else if filled & other[0] == 0 { other[0] } else { other[2] }
+            },
+        }
+    }
+
+    // every set of spots the first player can end up with, playing the spots of the rule in any order
+    fn outcomes(&self) -> Vec<u64> {
+        let mut outcomes = Vec::new();
+        self.play_out(0, 0, &mut outcomes);
+        outcomes.sort_unstable();
+        outcomes.dedup();
+        outcomes
+    }
+
+    fn play_out(&self, filled: u64, first: u64, outcomes: &mut Vec<u64>) {
+        let spots = self.spots();
+        if filled == spots { return outcomes.push(first) }
+        let mut open = spots & !filled;
+        while open != 0 {
+            let spot = open & open.wrapping_neg();
+            open &= open - 1;
+            if !playable(spots, filled, spot) { continue }
+            let answer = self.answer(filled, spot);
+            // the rules are built so this never happens, or the second player could not answer
+            debug_assert!(answer & spots & !filled & !spot != 0 && playable(spots, filled | spot, answer), "{self:?} can not answer {spot}");
+            self.play_out(filled | spot | answer, first | spot, outcomes);
+        }
+    }
+
+    // groups of the first player the rule refutes, as bits of GROUPS, the spots the second player is
+    // sure to get, and the outcomes (none are listed for claimevens and verticals, where the first
+    // player always gets the lower spot)
+    fn solves(&self) -> (u128, u64, Vec<u64>) {
+        if let Rule::Claimeven(_, upper) | Rule::Vertical(_, upper) = *self {
+            return (SPOT_GROUPS[upper.trailing_zeros() as usize], upper, Vec::new())
+        }
+        let spots = self.spots();
+        let outcomes = self.outcomes();
+        let mut refuted = 0;
+        for (i, group) in GROUPS.iter().enumerate() {
+            let inside = group & spots;
+            if inside != 0 && outcomes.iter().all(|first| inside & !first != 0) { refuted |= 1 << i }
+        }
+        let certain = spots & !outcomes.iter().fold(0, |all, first| all | first);
+        (refuted, certain, outcomes)
+    }
+
+    pub fn describe(&self) -> String {
+        let names = |spots: &[u64]| spots.iter().map(|&spot| name(spot)).collect::<Vec<String>>().join(" ");
+        match *self {
+            Rule::Claimeven(a, b) => format!("claimeven {}", names(&[a, b])),
+            Rule::Vertical(a, b) => format!("vertical {}", names(&[a, b])),
+            Rule::Baseinverse(a, b) => format!("baseinverse {}", names(&[a, b])),
+            Rule::Baseclaim(a, b, c, d) => format!("baseclaim {}", names(&[a, b, c, d])),
+            Rule::Lowinverse(x, y) => format!("lowinverse {}", names(&[x[0], x[1], y[0], y[1]])),
+            Rule::Highinverse(x, y) => format!("highinverse {}", names(&[x[0], x[1], x[2], y[0], y[1], y[2]])),
+        }
+    }
+}
+
+// whether a spot can be played once the filled spots of a rule are, assuming the spots below the
+// rule are filled by then
+fn playable(spots: u64, filled: u64, spot: u64) -> bool {
+    let column = 0b111111 << (spot.trailing_zeros() / 7 * 7);
+    spots & column & (spot - 1) & !filled == 0
+}
+
+// spots are named like in Allis' thesis, a1 is the bottom left
+fn name(spot: u64) -> String {
+    let bit = spot.trailing_zeros();
+    format!("{}{}", (b'a' + (bit / 7) as u8) as char, bit % 7 + 1)
+}
+
+pub struct Proof {
+    pub rules: Vec<Rule>,
+    pub afterevens: Vec<u64>, // groups of the second player that refute some group of the first player
+    pub befores: Vec<u64>, // the same for befores
+    pub specialbefores: Vec<u64>,
+    pub win: Option<u64>, // a group the second player is sure to complete, if it wins
+}
+
+impl Proof {
+    // the best score the first player can get
+    pub fn bound(&self) -> i8 {
+        if self.win.is_some() { -1 } else { 0 }
+    }
+}
+
+// finds proofs, remembering what the rules it tried solve for the next position
+#[derive(Default)]
+pub struct Prover {
+    solved: HashMap<Rule, (u128, u64, Vec<u64>)>,
+}
+
+impl Prover {
+    pub fn new() -> Prover {
+        Prover { solved: HashMap::new() }
+    }
+
+    // a proof that the second player at least draws, preferring one where it wins
+    pub fn prove(&mut self, pos: &Position) -> Option<Proof> {
+        if pos.rules != Rules::Standard || pos.turn != 0 || pos.moves.len() == 42 { return None }
+
+        // the empty spots of each column, from the bottom
+        let mut columns: Vec<Vec<u64>> = Vec::new();
+        for col in 0..7 {
+            let mut spots = Vec::new();
+            let mut spot = pos.get_col_height_mask(col);
+            while spot & ROW << 6 == 0 {
+                spots.push(spot);
+                spot <<= 1;
+            }
+            columns.push(spots);
+        }
+
+        let left = (0..7).filter(|&col| !columns[col].is_empty()).fold(0, |left, col| left | 1 << col);
+        let [first, second] = pos.board;
+        let firsts = (0..69).filter(|&i| GROUPS[i] & second == 0).fold(0, |firsts, i| firsts | 1 << i);
+        let seconds = GROUPS.iter().copied().filter(|&group| group & first == 0).collect();
+        let mut search = Search { pos, columns, solved: &mut self.solved, rules: Vec::new(), firsts, seconds, refuted: 0, certain: 0, best: None };
+        search.cover(left);
+        search.best
+    }
+}
+
+pub fn prove(pos: &Position) -> Option<Proof> {
+    Prover::new().prove(pos)
+}
+
+// the best score the first player to play can get, if a proof says so
+pub fn upper_bound(pos: &Position) -> Option<i8> {
+    prove(pos).map(|proof| proof.bound())
+}
+
+// a proof for people reading an analysis
+pub fn explain(pos: &Position) -> Vec<String> {
+    let Some(proof) = prove(pos) else { return Vec::new() };
+    let mut lines = vec![match proof.win {
+        Some(group) => format!("victor: the second player wins by completing {}", spot_names(group & !pos.board[1])),
+        None => String::from("victor: the second player draws at least"),
+    }];
+    lines.extend(proof.rules.iter().map(|rule| rule.describe()));
+    lines.extend(proof.afterevens.iter().map(|&group| format!("aftereven {}", spot_names(group & !pos.board[1]))));
+    lines.extend(proof.befores.iter().map(|&group| format!("before {}", spot_names(group & !pos.board[1]))));
+    lines.extend(proof.specialbefores.iter().map(|&group| format!("specialbefore {}", spot_names(group & !pos.board[1]))));
+    lines
+}
+
+fn spot_names(mut spots: u64) -> String {
+    let mut names = Vec::new();
+    while spots != 0 {
+        names.push(name(spots & spots.wrapping_neg()));
+        spots &= spots - 1;
+    }
+    names.join(" ")
+}
+
+struct Search<'a> {
+    pos: &'a Position,
+    columns: Vec<Vec<u64>>,
+    solved: &'a mut HashMap<Rule, (u128, u64, Vec<u64>)>, // what each rule tried so far solves
+    rules: Vec<Rule>,
+    firsts: u128, // groups the first player could still get, as bits of GROUPS
+    seconds: Vec<u64>, // groups the second player could still get
+    refuted: u128, // groups the rules so far refute
+    certain: u64, // spots the rules so far give the second player
+    best: Option<Proof>,
+}
+
+impl Search<'_> {
+    // tries every way of covering the lowest spots of the columns left, lowest column first
+    fn cover(&mut self, left: u8) {
+        if self.best.as_ref().is_some_and(|proof| proof.win.is_some()) { return }
+        if left == 0 { return self.check() }
+        let col = left.trailing_zeros() as usize;
+        let rest = left & (left - 1);
+        let spot = |search: &Self, col: usize, i: usize| search.columns[col][i];
+        let height = |search: &Self, col: usize| search.columns[col].len();
+        let odd = |search: &Self, col: usize| search.columns[col].len() % 2 == 1;
+
+        // nothing special, claimevens and verticals all the way up
+        if !odd(self, col) { self.with(None, &[(col, 0)], rest) }
+
+        for other in (0..7).filter(|&other| rest & 1 << other != 0) {
+            let without = rest & !(1 << other);
+            if odd(self, col) && odd(self, other) {
+                let rule = Rule::Baseinverse(spot(self, col, 0), spot(self, other, 0));
+                self.with(Some(rule), &[(col, 1), (other, 1)], without);
+                if height(self, col) >= 3 && height(self, other) >= 3 {
+                    let [x, y] = [col, other].map(|c| [0, 1, 2].map(|i| spot(self, c, i)));
+                    self.with(Some(Rule::Highinverse(x, y)), &[(col, 3), (other, 3)], without);
+                }
+            }
+            if !odd(self, col) && !odd(self, other) {
+                let [x, y] = [col, other].map(|c| [0, 1].map(|i| spot(self, c, i)));
+                self.with(Some(Rule::Lowinverse(x, y)), &[(col, 2), (other, 2)], without);
+            }
+
+            // baseclaims use a third column, where b needs an even column and a and c odd ones
+            for third in (other + 1..7).filter(|&third| without & 1 << third != 0) {
+                let without = without & !(1 << third);
+                for (a, b, c) in [(col, other, third), (other, col, third), (col, third, other)] {
+                    if odd(self, b) || !odd(self, a) || !odd(self, c) { continue }
+                    for (a, c) in [(a, c), (c, a)] {
+                        let rule = Rule::Baseclaim(spot(self, a, 0), spot(self, b, 0), spot(self, c, 0), spot(self, b, 1));
+                        self.with(Some(rule), &[(a, 1), (b, 2), (c, 1)], without);
+                    }
+                }
+            }
+        }
+    }
+
+    // adds a rule, and claimevens and verticals above the given number of spots of some columns
+    fn with(&mut self, rule: Option<Rule>, from: &[(usize, usize)], left: u8) {
+        let before = (self.rules.len(), self.refuted, self.certain);
+        let add = |search: &mut Self, rule: Rule| {
+            let (refuted, certain) = match rule {
+                Rule::Claimeven(_, upper) | Rule::Vertical(_, upper) => (SPOT_GROUPS[upper.trailing_zeros() as usize], upper),
+                _ => {
+                    let (refuted, certain, _) = search.solved.entry(rule).or_insert_with(|| rule.solves());
+                    (*refuted, *certain)
+                },
+            };
+            search.refuted |= refuted;
+            search.certain |= certain;
+            search.rules.push(rule);
+        };
+        if let Some(rule) = rule { add(self, rule) }
+        for &(col, start) in from {
+            for i in (start..self.columns[col].len()).step_by(2) {
+                let (lower, upper) = (self.columns[col][i], self.columns[col][i + 1]);
+                // the upper spot is on an even row when counting from 1
+                add(self, if upper.trailing_zeros() % 7 % 2 == 1 { Rule::Claimeven(lower, upper) } else { Rule::Vertical(lower, upper) });
+            }
+        }
+        self.cover(left);
+        self.rules.truncate(before.0);
+        (self.refuted, self.certain) = (before.1, before.2);
+    }
+
+    // whether the rules chosen refute every group of the first player
+    fn check(&mut self) {
+        let empty = !(self.pos.board[0] | self.pos.board[1]);
+
+        // groups of the second player it is sure to complete
+        let sure: Vec<u64> = self.seconds.iter().copied().filter(|&group| group & empty & !self.certain == 0).collect();
+
+        let (mut afterevens, mut befores, mut specialbefores) = (Vec::new(), Vec::new(), Vec::new());
+        let mut open = self.firsts & !self.refuted;
+        while open != 0 {
+            let group = GROUPS[open.trailing_zeros() as usize];
+            open &= open - 1;
+            if let Some(&after) = sure.iter().find(|&&after| is_after(group, after & empty)) {
+                if !afterevens.contains(&after) { afterevens.push(after) }
+                continue
+            }
+            match self.before(group, empty) {
+                Some((before, false)) => if !befores.contains(&before) { befores.push(before) },
+                Some((before, true)) => if !specialbefores.contains(&before) { specialbefores.push(before) },
+                None => return,
+            }
+        }
+
+        let win = sure.first().copied();
+        if self.best.is_none() || win.is_some() {
+            self.best = Some(Proof { rules: self.rules.clone(), afterevens, befores, specialbefores, win });
+        }
+    }
+
+    // a before group of the second player that refutes a group of the first player, and whether it
+    // is a specialbefore
+    fn before(&self, group: u64, empty: u64) -> Option<(u64, bool)> {
+        self.seconds.iter().copied().find_map(|before| {
+            let spots = before & empty;
+            let above = spots << 1;
+            if spots == 0 || spots & ROW << 5 != 0 || group & above != above { return None }
+            if self.rules.iter().any(|rule| self.allows(rule, group & empty, spots)) { return None }
+            // special if it fails with a baseinverse unpaired, so the first player could take both of
+            // its spots, which is when one of them is in the before group. since the before holds,
+            // the other is then in the first player's group
+            let special = self.rules.iter().any(|rule| matches!(rule, Rule::Baseinverse(..)) && rule.spots() & spots != 0);
+            Some((before, special))
+        })
+    }
+
+    // whether a rule lets the first player end up with every spot of a group that it covers, along
+    // with one of some other spots
+    fn allows(&self, rule: &Rule, group: u64, spots: u64) -> bool {
+        let (group, spots) = (group & rule.spots(), spots & rule.spots());
+        if spots == 0 { return false }
+        match *rule {
+            Rule::Claimeven(lower, _) | Rule::Vertical(lower, _) => group & !lower == 0 && lower & spots != 0,
+            _ => self.solved[rule].2.iter().any(|&first| first & group == group && first & spots != 0),
+        }
+    }
+
+}
+
+// whether a group has a spot above the empty spots of an aftereven group in every one of its
+// columns, so the aftereven group is complete before it can be
+fn is_after(group: u64, empty: u64) -> bool {
+    (0..7).all(|col| {
+        let column = 0b111111 << (7 * col);
+        let below = empty & column;
+        below == 0 || group & column & !(u64::MAX >> below.leading_zeros()) != 0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::read_test_file;
+    use crate::Solver;
+
+    fn spot(name: &str) -> u64 {
+        let bytes = name.as_bytes();
+        1 << (7 * (bytes[0] - b'a') as u32 + (bytes[1] - b'1') as u32)
+    }
+
+    #[test]
+    fn test_groups() {
+        assert_eq!(GROUPS.iter().filter(|group| group.count_ones() == 4).count(), 69);
+        for group in GROUPS {
+            assert_eq!((crate::zugzwang::ODD_ROWS | crate::zugzwang::EVEN_ROWS) & group, group);
+        }
+        let mut sorted = GROUPS.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 69);
+    }
+
+    #[test]
+    fn test_rules_solve() {
+        let (refuted, certain, _) = Rule::Claimeven(spot("a1"), spot("a2")).solves();
+        assert_eq!(certain, spot("a2"));
+        assert_eq!(refuted.count_ones(), GROUPS.iter().filter(|&&group| group & spot("a2") != 0).count() as u32);
+
+        // the second player gets one of the two spots
+        assert_eq!(Rule::Baseinverse(spot("a1"), spot("b1")).outcomes(), [spot("a1"), spot("b1")]);
+
+        // the first player never gets both of a and the spot above b, or both of b and c
+        let baseclaim = Rule::Baseclaim(spot("a1"), spot("b1"), spot("c1"), spot("b2"));
+        for first in baseclaim.outcomes() {
+            assert_eq!(first.count_ones(), 2);
+            assert_ne!(first, spot("a1") | spot("b2"));
+            assert_ne!(first, spot("b1") | spot("c1"));
+        }
+
+        let lowinverse = Rule::Lowinverse([spot("a1"), spot("a2")], [spot("b1"), spot("b2")]);
+        assert!(lowinverse.outcomes().iter().all(|first| (first & (spot("a2") | spot("b2"))).count_ones() == 1));
+
+        // the pairs of spots the first player can never both get
+        let highinverse = Rule::Highinverse([spot("a1"), spot("a2"), spot("a3")], [spot("b1"), spot("b2"), spot("b3")]);
+        let outcomes = highinverse.outcomes();
+        for pair in ["a3 b3", "a2 b2", "a2 a3", "b2 b3"] {
+            let pair = pair.split(' ').map(spot).fold(0, |all, spot| all | spot);
+            assert!(outcomes.iter().all(|first| first & pair != pair), "{}", spot_names(pair));
+        }
+    }
+
+    #[test]
+    fn test_proves_claimeven() {
+        // zugzwang's claimeven rule is a proof that only uses claimevens
+        let p = Position::from_moves("31351514213474435441723165222257767735").unwrap();
+        let proof = prove(&p).unwrap();
+        assert!(proof.bound() <= 0);
+        assert!(explain(&p)[0].starts_with("victor"));
+        assert!(prove(&Position::from_moves("4").unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_proves_before() {
+        let p = Position::from_moves("4526317466612662").unwrap();
+        let proof = prove(&p).unwrap();
+        assert!(!proof.befores.is_empty() && proof.specialbefores.is_empty() && proof.bound() == 0);
+        assert!(explain(&p).contains(&"before a4 b4 c4 d4".to_string()));
+        let p = Position::from_moves("3151372573145557").unwrap();
+        assert_eq!(prove(&p).unwrap().specialbefores, [spot("a1") | spot("b2") | spot("c3") | spot("d4")]);
+    }
+
+    #[test]
+    fn test_solver_with_victor() {
+        let mut solver = Solver::new();
+        solver.set_victor(true);
+        for moves in ["2252576253462244111563365343671351441", "7422341735647741166133573473242566", "274552224131661", "66513447136647211277114"] {
+            let mut p = Position::from_moves(moves).unwrap();
+            assert_eq!(solver.score(&mut p.clone()).unwrap().0, Solver::new().score(&mut p).unwrap().0, "{moves}");
+        }
+    }
+
+    #[test]
+    fn test_bounds_are_sound() {
+        let mut proven = [0; 2];
+        let mut solver = Solver::new();
+        for file in ["End-Easy", "Middle-Easy"] {
+            for case in read_test_file(format!("test_files/{file}.txt")).unwrap().iter().step_by(3) {
+                let mut pos = Position::from_moves(&case.moves).unwrap();
+                // the earlier positions of the game too, which have no score in the test file
+                while pos.num_moves() > 16 {
+                    if let Some(bound) = upper_bound(&pos) {
+                        proven[(-bound) as usize] += 1;
+                        let score = if pos.num_moves() == case.moves.len() { case.score } else { solver.score(&mut pos.clone()).unwrap().0 };
+                        assert!(score <= bound, "{} proves {bound}: {:?}", pos.moves.iter().map(|mv| (mv + 1).to_string()).collect::<String>(), explain(&pos));
+                    }
+                    pos.undo_move();
+                }
+            }
+        }
+        assert!(proven[0] > 0 && proven[1] > 0, "{proven:?}");
+    }
+
+}
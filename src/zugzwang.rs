@@ -0,0 +1,159 @@
+// knowledge about zugzwang from connect 4 theory (Victor Allis, A Knowledge-based Approach of
+// Connect-Four, 1988). if one player keeps answering the other by playing on top of their move,
+// each column fills up in pairs, and whoever moves first into a pair never gets its upper spot.
+// when the board fills up that way the first player gets the spots on odd rows and the second
+// player the spots on even rows (counting from 1 at the bottom), so threats on odd rows are the
+// ones that win for the first player and threats on even rows the ones that win for the second.
+//
+// two of the rules are used to bound scores without searching, only for regular connect 4:
+//   claimeven   the first player is to play and every column has an even number of empty spots.
+//               the second player answers every move on top of it, leaving the first player only
+//               the empty spots on odd rows. if those can not make four in a row with the first
+//               player's stones, the first player can not win
+//   odd threat  the second player is to play, one column has an odd number of empty spots and the
+//               first player has a threat on an odd row of it. the first player answers every move
+//               on top of it, which gives the first player the odd rows of that column and the
+//               second player the odd rows of every other column. eventually the second player has
+//               to play below the threat, so unless the spots the second player gets before then
+//               make four in a row, the first player wins
+
+use crate::{get_threats, Position, Rules};
+
+// one bit for every spot in the given row, counting from 0 at the bottom
+const ROW: u64 = 4432676798593;
+
+// every spot on the board, without the extra row on top of each column
+const BOARD: u64 = ROW * 0b111111;
+
+// rows 1, 3 and 5 when counting from 1 at the bottom (so bitboard rows 0, 2 and 4)
+pub const ODD_ROWS: u64 = ROW | ROW << 2 | ROW << 4;
+
+// rows 2, 4 and 6 when counting from 1 at the bottom
+pub const EVEN_ROWS: u64 = ROW << 1 | ROW << 3 | ROW << 5;
+
+// splits threats into the ones on odd rows and the ones on even rows
+pub fn by_parity(threats: u64) -> (u64, u64) {
+    (threats & ODD_ROWS, threats & EVEN_ROWS)
+}
+
+// the best score the player to play can get, if one of the rules decides it without searching
+pub fn upper_bound(pos: &Position) -> Option<i8> {
+    match rule(pos)? {
+        Rule::ClaimEven => Some(0),
+        // the first player wins before the board is full, since the threat has a spot above it
+        Rule::OddThreat(_) => Some(-1),
+    }
+}
+
+// which rule decides a position, if any
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rule {
+    ClaimEven, // the first player can not win
+    OddThreat(usize), // the first player wins with the odd threat in this column
+}
+
+pub fn rule(pos: &Position) -> Option<Rule> {
+    if pos.rules != Rules::Standard { return None }
+    let empty = BOARD & !(pos.board[0] | pos.board[1]);
+
+    // a column has an odd number of empty spots when its next spot is on an even row
+    let odd_columns = pos.height_mask & EVEN_ROWS;
+
+    if odd_columns == 0 {
+        // every column is even, so it is the first player to play
+        if !has_four(pos.board[0] | empty & ODD_ROWS) { return Some(Rule::ClaimEven) }
+    } else if pos.turn == 1 && odd_columns.count_ones() == 1 {
+        let col = (odd_columns.trailing_zeros() / 7) as usize;
+        let column = 0b111111 << (7 * col);
+        let odd_threats = get_threats(pos.board, 0) & ODD_ROWS & column;
+        if odd_threats == 0 { return None }
+        let below = (1 << odd_threats.trailing_zeros()) - 1; // every spot below the lowest odd threat
+        let second = pos.board[1] | empty & ODD_ROWS & !column | empty & EVEN_ROWS & column & below;
+        if !has_four(second) { return Some(Rule::OddThreat(col)) }
+    }
+    None
+}
+
+// what the rules say about a position, for people reading an analysis
+pub fn explain(pos: &Position) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (player, name) in [(0, "first"), (1, "second")] {
+        let (odd, even) = by_parity(pos.threats(pos.board, player));
+        if odd | even != 0 {
+            lines.push(format!("{name} player has {} odd threats and {} even threats", odd.count_ones(), even.count_ones()));
+        }
+    }
+    match rule(pos) {
+        Some(Rule::ClaimEven) => lines.push(String::from("claimeven: the second player answers on top of every move, so the first player can not win")),
+        Some(Rule::OddThreat(col)) => lines.push(format!("odd threat: the first player answers on top of every move and wins with the odd threat in column {}", col + 1)),
+        None => (),
+    }
+    lines
+}
+
+// whether the stones make four in a row, for a board without the extra row on top
+fn has_four(b: u64) -> bool {
+    (b & b << 1 & b << 2 & b << 3) |
+    (b & b << 7 & b << 14 & b << 21) |
+    (b & b << 6 & b << 12 & b << 18) |
+    (b & b << 8 & b << 16 & b << 24) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::read_test_file;
+    use crate::{score, Solver};
+
+    #[test]
+    fn test_by_parity() {
+        // a spot on row 1 of column 4 and one on row 2 of column 7
+        assert_eq!(by_parity(1 << 21 | 1 << 43), (1 << 21, 1 << 43));
+        assert_eq!(by_parity(0), (0, 0));
+    }
+
+    #[test]
+    fn test_claimeven() {
+        let mut p = Position::from_moves("31351514213474435441723165222257767735").unwrap();
+        assert_eq!(rule(&p), Some(Rule::ClaimEven));
+        assert!(score(&mut p).0 <= 0);
+        assert!(rule(&Position::with_rules(Rules::Cylinder)).is_none());
+    }
+
+    #[test]
+    fn test_odd_threat() {
+        let mut p = Position::from_moves("66513447136647211277114").unwrap();
+        assert_eq!(rule(&p), Some(Rule::OddThreat(4)));
+        assert!(score(&mut p).0 <= -1);
+    }
+
+    #[test]
+    fn test_bounds_are_sound() {
+        let mut decided = 0;
+        let mut solver = Solver::new();
+        for file in ["End-Easy", "Middle-Easy"] {
+            for case in read_test_file(format!("test_files/{file}.txt")).unwrap() {
+                let mut pos = Position::from_moves(&case.moves).unwrap();
+                // the earlier positions of the game too, which have no score in the test file
+                while pos.num_moves() > 0 {
+                    if let Some(bound) = upper_bound(&pos) {
+                        decided += 1;
+                        let score = if pos.num_moves() == case.moves.len() { case.score } else { solver.score(&mut pos.clone()).unwrap().0 };
+                        assert!(score <= bound, "{:?} {:?}", pos.moves, rule(&pos));
+                    }
+                    pos.undo_move();
+                }
+            }
+        }
+        assert!(decided > 300);
+    }
+
+    #[test]
+    fn test_explain() {
+        let p = Position::from_moves("66513447136647211277114").unwrap();
+        let lines = explain(&p);
+        assert_eq!(lines[0], "first player has 2 odd threats and 0 even threats");
+        assert!(lines[1].ends_with("odd threat in column 5"));
+        assert!(explain(&Position::new()).is_empty());
+    }
+}